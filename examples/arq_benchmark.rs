@@ -0,0 +1,73 @@
+use proto_lab::{
+    ArqMode, ArqReceiver, ArqSender, IODriverSimulator, NetworkSimulator, WirelessModemFake,
+};
+
+const MESSAGE_COUNT: u8 = 50;
+const MESSAGE_LENGTH: usize = 32;
+const TIMEOUT_MS: u64 = 120;
+const SIMULATION_TIMEOUT_TICKS: u64 = 50_000;
+
+/// Sends the messages with the ARQ mode over the ether,
+/// where the jammer corrupts the frames from time to time.
+fn run(mode: ArqMode, jamming_period: u64) {
+    let simulator = NetworkSimulator::new(1);
+    simulator.create_ether("air");
+    let mut ether = simulator.get_ether("air").expect("Failed to find ether");
+
+    let sender_modem = WirelessModemFake::new("sender");
+    let receiver_modem = WirelessModemFake::new("receiver");
+    let jammer_modem = WirelessModemFake::new("jammer");
+    ether.register_driver(sender_modem.clone());
+    ether.register_driver(receiver_modem.clone());
+    ether.register_driver(jammer_modem.clone());
+
+    let sender = ArqSender::new(mode, TIMEOUT_MS, jamming_period);
+    let receiver = ArqReceiver::new(mode);
+    simulator.register_application(sender_modem, sender.clone());
+    simulator.register_application(receiver_modem, receiver.clone());
+    simulator.register_application(
+        jammer_modem,
+        move |modem: &mut WirelessModemFake, now: u64| {
+            while modem.get_from_tx_pin().is_some() {}
+            if now.is_multiple_of(jamming_period) {
+                modem.put_to_rx_pin(0x55);
+            }
+        },
+    );
+
+    for index in 0..MESSAGE_COUNT {
+        sender.send(&[index; MESSAGE_LENGTH]);
+    }
+    while !sender.is_idle() && simulator.get_current_tick() < SIMULATION_TIMEOUT_TICKS {
+        simulator.tick();
+    }
+
+    let stats = sender.get_stats();
+    println!(
+        "{:<40} jam every {:>4} ms: delivered {:>3}/{}, sent {:>4} frames, retransmitted {:>4}, \
+         average latency {:>8.1} ms, throughput {:>6.1} bit/s over {:>6} ms",
+        format!("{:?}", mode),
+        jamming_period,
+        receiver.get_delivered_count(),
+        MESSAGE_COUNT,
+        stats.sent_frames,
+        stats.retransmitted_frames,
+        stats.get_average_latency_ms().unwrap_or(f64::NAN),
+        stats
+            .get_throughput_bps(simulator.get_virtual_time_ms())
+            .unwrap_or(0.0),
+        simulator.get_virtual_time_ms(),
+    );
+}
+
+fn main() {
+    for jamming_period in [1000, 200, 80] {
+        for mode in [
+            ArqMode::StopAndWait,
+            ArqMode::GoBackN { window: 8 },
+            ArqMode::SelectiveRepeat { window: 8 },
+        ] {
+            run(mode, jamming_period);
+        }
+    }
+}
@@ -1,22 +1,36 @@
-use std::time::Instant;
+use std::{cell::RefCell, rc::Rc};
 
 use embedded_nano_mesh::{ms, ExactAddressType, Node, NodeConfig, NodeString};
-use proto_lab::{NetworkSimulator, WirelessModemFake};
+use proto_lab::{Application, NetworkSimulator, WirelessModemFake};
 
 const NODE_1_LISTEN_PERIOD: ms = 1;
 const NODE_2_LISTEN_PERIOD: ms = 1;
 const NODE_3_LISTEN_PERIOD: ms = 1;
 
+const SIMULATION_TIMEOUT_TICKS: u64 = 200;
+
+/// Runs the mesh node on its modem in lock-step with the simulator.
+/// Node is shared to let the scenario send and receive messages between ticks.
+struct MeshApplication {
+    node: Rc<RefCell<Node>>,
+}
+
+impl Application for MeshApplication {
+    fn update(&mut self, modem: &mut WirelessModemFake, now: u64) {
+        let _ = self.node.borrow_mut().update(modem, now as ms);
+    }
+}
+
 fn main() {
     /* Create simulator, ether, and devices registered in that ether. */
-    let mut simulator = NetworkSimulator::new(1);
+    let simulator = NetworkSimulator::new(1);
 
     simulator.create_ether("1");
     simulator.create_ether("2");
 
-    let mut driver_1 = WirelessModemFake::new("1");
-    let mut driver_2 = WirelessModemFake::new("2");
-    let mut driver_3 = WirelessModemFake::new("3");
+    let driver_1 = WirelessModemFake::new("1");
+    let driver_2 = WirelessModemFake::new("2");
+    let driver_3 = WirelessModemFake::new("3");
 
     {
         let mut ether_1 = simulator.get_ether("1").expect("Failed to find ether 1");
@@ -30,51 +44,61 @@ fn main() {
     }
 
     /* Create tested nodes. */
-    let mut mesh_node_1 = Node::new(NodeConfig {
+    let mesh_node_1 = Rc::new(RefCell::new(Node::new(NodeConfig {
         device_address: ExactAddressType::try_from(1).expect("1 equals to 0"),
         listen_period: NODE_1_LISTEN_PERIOD,
-    });
+    })));
 
-    let mut mesh_node_2 = Node::new(NodeConfig {
+    let mesh_node_2 = Rc::new(RefCell::new(Node::new(NodeConfig {
         device_address: ExactAddressType::try_from(2).expect("2 equals to 0"),
         listen_period: NODE_2_LISTEN_PERIOD,
-    });
+    })));
 
-    let mut mesh_node_3 = Node::new(NodeConfig {
+    let mesh_node_3 = Rc::new(RefCell::new(Node::new(NodeConfig {
         device_address: ExactAddressType::try_from(3).expect("3 equals to 0"),
         listen_period: NODE_3_LISTEN_PERIOD,
-    });
+    })));
+
+    simulator.register_application(
+        driver_1,
+        MeshApplication {
+            node: Rc::clone(&mesh_node_1),
+        },
+    );
+    simulator.register_application(
+        driver_2,
+        MeshApplication {
+            node: Rc::clone(&mesh_node_2),
+        },
+    );
+    simulator.register_application(
+        driver_3,
+        MeshApplication {
+            node: Rc::clone(&mesh_node_3),
+        },
+    );
 
     /* Do testing scenario */
-    let _ = mesh_node_1.send_to_exact(
+    let _ = mesh_node_1.borrow_mut().send_to_exact(
         NodeString::try_from("Message from node 1")
             .expect("Fail to pack message")
             .into_bytes(),
         ExactAddressType::try_from(3).expect("3 is 0"),
-        2.into(),
+        2,
         false,
     );
 
-    simulator.start_simulation_thread();
-
-    let start_time = Instant::now();
     loop {
-        let current_time = Instant::now().duration_since(start_time).as_millis() as ms;
-
-        let _ = mesh_node_1.update(&mut driver_1, current_time);
-        let _ = mesh_node_2.update(&mut driver_2, current_time);
-        let _ = mesh_node_3.update(&mut driver_3, current_time);
+        simulator.tick();
 
-        if current_time >= 200 {
+        if simulator.get_current_tick() >= SIMULATION_TIMEOUT_TICKS {
             panic!("Simulation timeout");
         }
 
-        if let Some(_packet) = mesh_node_3.receive() {
+        if let Some(_packet) = mesh_node_3.borrow_mut().receive() {
             break;
         }
     }
 
-    simulator.stop_simulation_thread();
-
-    println!("Simulation done");
+    println!("Simulation done in {} ticks", simulator.get_current_tick());
 }
@@ -0,0 +1,71 @@
+use proto_lab::{MacNode, MacProtocol, NetworkSimulator, WirelessModemFake};
+
+const NODE_COUNT: u16 = 6;
+const PAYLOAD_LENGTH: usize = 10;
+const SIMULATION_TICKS: u64 = 20_000;
+
+/// Loads the ether with the nodes running the MAC protocol, and reports
+/// frames received by the sink, frames lost to collisions, and the ether collisions.
+fn run(protocol: MacProtocol, arrival_probability: f64) {
+    let simulator = NetworkSimulator::new(1);
+    simulator.create_ether("air");
+    let mut ether = simulator.get_ether("air").expect("Failed to find ether");
+
+    let sink = MacNode::new(protocol, 0, 0);
+    let sink_modem = WirelessModemFake::new("sink");
+    ether.register_driver(sink_modem.clone());
+    simulator.register_application(sink_modem, sink.clone());
+
+    let mut nodes = Vec::new();
+    for node_id in 1..=NODE_COUNT {
+        let node = MacNode::new(protocol, node_id, node_id as u64);
+        node.set_traffic(arrival_probability, PAYLOAD_LENGTH, node_id as u64 + 1000);
+        let modem = WirelessModemFake::new(&format!("node_{}", node_id));
+        ether.register_driver(modem.clone());
+        simulator.register_application(modem, node.clone());
+        nodes.push(node);
+    }
+
+    simulator.run_ticks(SIMULATION_TICKS);
+
+    let offered: u64 = nodes
+        .iter()
+        .map(|node| node.get_stats().offered_frames)
+        .sum();
+    let transmitted: u64 = nodes
+        .iter()
+        .map(|node| node.get_stats().transmitted_frames)
+        .sum();
+    let sink_stats = sink.get_stats();
+    let delivered = sink.get_received_frames_by_sender();
+    println!(
+        "{:<50} load {:.3}: offered {:>5}, transmitted {:>5}, delivered {:>5} ({} to {} per node), \
+         collided {:>5}, corrupted {:>5}, collision ticks {:>6}, goodput {:>6.1} bit/s",
+        format!("{:?}", protocol),
+        arrival_probability,
+        offered,
+        transmitted,
+        sink_stats.received_frames,
+        delivered.values().min().unwrap_or(&0),
+        delivered.values().max().unwrap_or(&0),
+        sink_stats.collided_frames,
+        sink_stats.corrupted_frames,
+        ether.get_collision_count(),
+        sink_stats.received_bytes as f64 * 8.0 * 1000.0 / SIMULATION_TICKS as f64,
+    );
+}
+
+fn main() {
+    for arrival_probability in [0.002, 0.01, 0.03] {
+        for protocol in [
+            MacProtocol::Aloha,
+            MacProtocol::SlottedAloha { slot_ms: 20 },
+            MacProtocol::CsmaCa {
+                difs_ticks: 2,
+                contention_window: 16,
+            },
+        ] {
+            run(protocol, arrival_probability);
+        }
+    }
+}
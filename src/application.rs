@@ -0,0 +1,24 @@
+use crate::WirelessModemFake;
+
+/// Protocol instance, which runs on a device in lock-step with the simulator.
+/// Register it with `NetworkSimulator::register_application`, and the simulator will call
+/// `update` once per tick right before the tick is simulated, so device I/O, protocol logic
+/// and ether simulation are interleaved deterministically in one thread.
+pub trait Application {
+    /// Gives the application a chance to read from and write into its modem.
+    /// `now` is the virtual time of the simulator in milliseconds.
+    fn update(&mut self, modem: &mut WirelessModemFake, now: u64);
+
+    /// Is called when the modem is powered on again after being powered off, crashed
+    /// or rebooted, to let the application drop the state, which would be lost by a real device.
+    fn reboot(&mut self) {}
+}
+
+impl<F> Application for F
+where
+    F: FnMut(&mut WirelessModemFake, u64),
+{
+    fn update(&mut self, modem: &mut WirelessModemFake, now: u64) {
+        self(modem, now)
+    }
+}
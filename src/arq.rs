@@ -0,0 +1,652 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    rng::Rng, Application, FrameDecoder, Framing, HdlcCrc, IODriverSimulator, WirelessModemFake,
+};
+
+const DATA_FRAME: u8 = 0;
+const ACK_FRAME: u8 = 1;
+
+/// Count of ticks the channel shall be idle before the sender transmits,
+/// which leaves the receiver room for acknowledgements on the half duplex channel.
+const SENDER_IDLE_TICKS: u64 = 3;
+
+/// Offsets from the expected sequence number starting from this one are behind it,
+/// so the frames were already delivered.
+const SEQUENCE_HALF_RANGE: u16 = 0x8000;
+
+/// Framing of ARQ frames. Frame check sequence lets corrupted frames be dropped.
+const ARQ_FRAMING: Framing = Framing::Hdlc(HdlcCrc::Crc16);
+
+/// Retransmission strategy of the ARQ protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArqMode {
+    /// Only one frame is waiting for acknowledgement at once.
+    StopAndWait,
+    /// Receiver accepts only in order frames and acknowledges cumulatively.
+    /// Sender retransmits all the frames of the window on timeout.
+    GoBackN { window: u16 },
+    /// Receiver buffers out of order frames and acknowledges each one.
+    /// Sender retransmits only the timed out frames.
+    SelectiveRepeat { window: u16 },
+}
+
+impl ArqMode {
+    fn get_window(&self) -> u16 {
+        let window = match self {
+            ArqMode::StopAndWait => 1,
+            ArqMode::GoBackN { window } | ArqMode::SelectiveRepeat { window } => *window,
+        };
+        if window == 0 || window > u16::MAX / 2 {
+            panic!("ARQ window of {} frames is not supported", window);
+        }
+        window
+    }
+}
+
+/// Figures of the ARQ sender.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ArqStats {
+    /// Count of data frames written into the modem, including retransmissions.
+    pub sent_frames: u64,
+    pub retransmitted_frames: u64,
+    /// Count of messages acknowledged by the receiver.
+    pub acknowledged_messages: u64,
+    pub acknowledged_bytes: u64,
+    /// Sum of times from `send` call till acknowledgement of every message.
+    pub total_latency_ms: u64,
+    pub max_latency_ms: u64,
+    /// Time of the first `send` call.
+    pub first_send_ms: Option<u64>,
+    /// Time of the last acknowledgement.
+    pub last_acknowledgement_ms: Option<u64>,
+}
+
+impl ArqStats {
+    pub fn get_average_latency_ms(&self) -> Option<f64> {
+        match self.acknowledged_messages {
+            0 => None,
+            count => Some(self.total_latency_ms as f64 / count as f64),
+        }
+    }
+
+    /// Gets count of acknowledged payload bits per second of virtual time
+    /// from the first `send` call till `now_ms`, like the current time of the simulator.
+    /// Time spent on messages, which are not acknowledged yet, lowers the throughput,
+    /// so a stalled transfer does not look fast.
+    pub fn get_throughput_bps(&self, now_ms: u64) -> Option<f64> {
+        match self.first_send_ms {
+            Some(first) if now_ms > first => {
+                Some(self.acknowledged_bytes as f64 * 8.0 * 1000.0 / (now_ms - first) as f64)
+            }
+            _ => None,
+        }
+    }
+}
+
+fn encode_frame(kind: u8, sequence: u16, payload: &[u8]) -> Vec<u8> {
+    let mut frame = vec![kind];
+    frame.extend_from_slice(&sequence.to_be_bytes());
+    frame.extend_from_slice(payload);
+    ARQ_FRAMING.encode(&frame)
+}
+
+fn parse_frame(frame: &[u8]) -> Option<(u8, u16, &[u8])> {
+    match frame {
+        [kind, sequence_high, sequence_low, payload @ ..] => Some((
+            *kind,
+            u16::from_be_bytes([*sequence_high, *sequence_low]),
+            payload,
+        )),
+        _ => None,
+    }
+}
+
+fn write_frame(modem: &WirelessModemFake, frame: Vec<u8>) {
+    for byte in frame {
+        modem.put_to_rx_pin(byte);
+    }
+}
+
+struct Outstanding {
+    sequence: u16,
+    payload: Vec<u8>,
+    submitted_at: u64,
+    sent_at: Option<u64>,
+    /// Timeout of the latest transmission, including its jitter.
+    timeout_ms: u64,
+    transmissions: u32,
+    acknowledged: bool,
+}
+
+struct SenderState {
+    mode: ArqMode,
+    timeout_ms: u64,
+    rng: Rng,
+    decoder: FrameDecoder,
+    pending: VecDeque<(Vec<u8>, u64)>,
+    outstanding: VecDeque<Outstanding>,
+    next_sequence: u16,
+    now: u64,
+    idle_ticks: u64,
+    stats: ArqStats,
+}
+
+/// Sending side of the reference ARQ protocol, which runs as `Application` on its modem.
+/// It is meant for one sender and one `ArqReceiver` sharing the ether.
+/// Frames are written into the modem only once the channel is idle for a few ticks,
+/// and are retransmitted if not acknowledged within `timeout_ms` after being written.
+/// Every timeout gets random jitter of up to half of `timeout_ms` on top,
+/// so retransmissions do not lock in step with periodic interference.
+/// The timeout shall be longer than airtime of the data frame and the acknowledgement.
+///
+/// Register a clone of it, and keep the other one to send messages and read the figures.
+pub struct ArqSender {
+    state: Arc<Mutex<SenderState>>,
+}
+
+impl ArqSender {
+    /// Creates the sender. `seed` makes the jitter of timeouts reproducible.
+    pub fn new(mode: ArqMode, timeout_ms: u64, seed: u64) -> Self {
+        mode.get_window();
+        ArqSender {
+            state: Arc::new(Mutex::new(SenderState {
+                mode,
+                timeout_ms,
+                rng: Rng::new(seed),
+                decoder: FrameDecoder::new(ARQ_FRAMING),
+                pending: VecDeque::new(),
+                outstanding: VecDeque::new(),
+                next_sequence: 0,
+                now: 0,
+                idle_ticks: 0,
+                stats: ArqStats::default(),
+            })),
+        }
+    }
+
+    fn lock_state(&self) -> std::sync::MutexGuard<'_, SenderState> {
+        self.state.lock().expect("Fail to lock ARQ sender state")
+    }
+
+    /// Queues the message to be delivered to the receiver.
+    pub fn send(&self, payload: &[u8]) {
+        let mut state = self.lock_state();
+        let now = state.now;
+        state.stats.first_send_ms.get_or_insert(now);
+        state.pending.push_back((payload.to_vec(), now));
+    }
+
+    /// Tells if all the queued messages are acknowledged.
+    pub fn is_idle(&self) -> bool {
+        let state = self.lock_state();
+        state.pending.is_empty() && state.outstanding.is_empty()
+    }
+
+    pub fn get_stats(&self) -> ArqStats {
+        self.lock_state().stats
+    }
+}
+
+impl SenderState {
+    fn acknowledge_front(&mut self) {
+        while self
+            .outstanding
+            .front()
+            .is_some_and(|outstanding| outstanding.acknowledged)
+        {
+            let outstanding = self.outstanding.pop_front().expect("Front shall exist");
+            let latency = self.now - outstanding.submitted_at;
+            self.stats.acknowledged_messages += 1;
+            self.stats.acknowledged_bytes += outstanding.payload.len() as u64;
+            self.stats.total_latency_ms += latency;
+            self.stats.max_latency_ms = self.stats.max_latency_ms.max(latency);
+            self.stats.last_acknowledgement_ms = Some(self.now);
+        }
+    }
+
+    fn receive_acknowledgement(&mut self, sequence: u16) {
+        let base = match self.outstanding.front() {
+            Some(outstanding) => outstanding.sequence,
+            None => return,
+        };
+        match self.mode {
+            // Acknowledgement carries the next expected sequence number.
+            ArqMode::StopAndWait | ArqMode::GoBackN { .. } => {
+                let count = sequence.wrapping_sub(base) as usize;
+                if count <= self.outstanding.len() {
+                    for outstanding in self.outstanding.iter_mut().take(count) {
+                        outstanding.acknowledged = true;
+                    }
+                }
+            }
+            ArqMode::SelectiveRepeat { .. } => {
+                if let Some(outstanding) = self
+                    .outstanding
+                    .iter_mut()
+                    .find(|outstanding| outstanding.sequence == sequence)
+                {
+                    outstanding.acknowledged = true;
+                }
+            }
+        }
+        self.acknowledge_front();
+    }
+
+    fn next_frame_to_send(&mut self) -> Option<usize> {
+        let now = self.now;
+        let is_timed_out = |outstanding: &Outstanding| {
+            !outstanding.acknowledged
+                && matches!(outstanding.sent_at, Some(sent_at) if now - sent_at >= outstanding.timeout_ms)
+        };
+
+        match self.mode {
+            ArqMode::StopAndWait | ArqMode::GoBackN { .. } => {
+                if self.outstanding.front().is_some_and(is_timed_out) {
+                    for outstanding in self.outstanding.iter_mut() {
+                        outstanding.sent_at = None;
+                    }
+                }
+                self.outstanding
+                    .iter()
+                    .position(|outstanding| outstanding.sent_at.is_none())
+            }
+            ArqMode::SelectiveRepeat { .. } => {
+                if let Some(position) = self.outstanding.iter().position(is_timed_out) {
+                    return Some(position);
+                }
+                self.outstanding
+                    .iter()
+                    .position(|outstanding| outstanding.sent_at.is_none())
+            }
+        }
+    }
+}
+
+impl Clone for ArqSender {
+    fn clone(&self) -> Self {
+        ArqSender {
+            state: Arc::clone(&self.state),
+        }
+    }
+}
+
+impl Application for ArqSender {
+    fn update(&mut self, modem: &mut WirelessModemFake, now: u64) {
+        let mut state = self.lock_state();
+        state.now = now;
+
+        while let Some(byte) = modem.get_from_tx_pin() {
+            if let Some(frame) = state.decoder.push(byte) {
+                if let Some((ACK_FRAME, sequence, _)) = parse_frame(&frame) {
+                    state.receive_acknowledgement(sequence);
+                }
+            }
+        }
+
+        let window = state.mode.get_window() as usize;
+        while state.outstanding.len() < window {
+            let (payload, submitted_at) = match state.pending.pop_front() {
+                Some(pending) => pending,
+                None => break,
+            };
+            let sequence = state.next_sequence;
+            state.next_sequence = sequence.wrapping_add(1);
+            state.outstanding.push_back(Outstanding {
+                sequence,
+                payload,
+                submitted_at,
+                sent_at: None,
+                timeout_ms: 0,
+                transmissions: 0,
+                acknowledged: false,
+            });
+        }
+
+        match modem.is_busy() || modem.is_receiving() {
+            true => state.idle_ticks = 0,
+            false => state.idle_ticks += 1,
+        }
+        if state.idle_ticks < SENDER_IDLE_TICKS {
+            return;
+        }
+
+        if let Some(position) = state.next_frame_to_send() {
+            state.idle_ticks = 0;
+            let jitter_ms = match state.timeout_ms / 2 {
+                0 => 0,
+                max_jitter_ms => state.rng.next_u64() % (max_jitter_ms + 1),
+            };
+            let timeout_ms = state.timeout_ms + jitter_ms;
+            let outstanding = &mut state.outstanding[position];
+            outstanding.sent_at = Some(now);
+            outstanding.timeout_ms = timeout_ms;
+            outstanding.transmissions += 1;
+            let retransmission = outstanding.transmissions > 1;
+            write_frame(
+                modem,
+                encode_frame(DATA_FRAME, outstanding.sequence, &outstanding.payload),
+            );
+            state.stats.sent_frames += 1;
+            if retransmission {
+                state.stats.retransmitted_frames += 1;
+            }
+        }
+    }
+
+    /// Messages waiting for acknowledgement are lost with the state of the device.
+    fn reboot(&mut self) {
+        let mut state = self.lock_state();
+        state.outstanding.clear();
+        state.pending.clear();
+        state.decoder = FrameDecoder::new(ARQ_FRAMING);
+    }
+}
+
+struct ReceiverState {
+    mode: ArqMode,
+    decoder: FrameDecoder,
+    expected_sequence: u16,
+    out_of_order: BTreeMap<u16, Vec<u8>>,
+    pending_acknowledgements: Vec<u16>,
+    delivered: VecDeque<Vec<u8>>,
+    delivered_messages: u64,
+    duplicate_frames: u64,
+    discarded_frames: u64,
+}
+
+/// Receiving side of the reference ARQ protocol, which runs as `Application` on its modem.
+/// Messages are delivered in order and without duplicates.
+/// Acknowledgements are sent as soon as the channel gets idle.
+/// ```
+/// use proto_lab::{ArqMode, ArqReceiver, ArqSender, NetworkSimulator, WirelessModemFake};
+///
+/// let simulator = NetworkSimulator::new(1);
+/// simulator.create_ether("air");
+/// let mut ether = simulator.get_ether("air").unwrap();
+/// let sender_modem = WirelessModemFake::new("sender");
+/// let receiver_modem = WirelessModemFake::new("receiver");
+/// ether.register_driver(sender_modem.clone());
+/// ether.register_driver(receiver_modem.clone());
+///
+/// let mode = ArqMode::GoBackN { window: 4 };
+/// let sender = ArqSender::new(mode, 40, 0);
+/// let receiver = ArqReceiver::new(mode);
+/// simulator.register_application(sender_modem, sender.clone());
+/// simulator.register_application(receiver_modem, receiver.clone());
+///
+/// for index in 0..5u8 {
+///     sender.send(&[index; 8]);
+/// }
+/// simulator.run_ticks(500);
+///
+/// assert!(sender.is_idle());
+/// assert_eq!(sender.get_stats().acknowledged_messages, 5);
+/// for index in 0..5u8 {
+///     assert_eq!(receiver.receive(), Some(vec![index; 8]));
+/// }
+/// ```
+pub struct ArqReceiver {
+    state: Arc<Mutex<ReceiverState>>,
+}
+
+impl ArqReceiver {
+    pub fn new(mode: ArqMode) -> Self {
+        mode.get_window();
+        ArqReceiver {
+            state: Arc::new(Mutex::new(ReceiverState {
+                mode,
+                decoder: FrameDecoder::new(ARQ_FRAMING),
+                expected_sequence: 0,
+                out_of_order: BTreeMap::new(),
+                pending_acknowledgements: Vec::new(),
+                delivered: VecDeque::new(),
+                delivered_messages: 0,
+                duplicate_frames: 0,
+                discarded_frames: 0,
+            })),
+        }
+    }
+
+    fn lock_state(&self) -> std::sync::MutexGuard<'_, ReceiverState> {
+        self.state.lock().expect("Fail to lock ARQ receiver state")
+    }
+
+    /// Takes the oldest delivered message.
+    pub fn receive(&self) -> Option<Vec<u8>> {
+        self.lock_state().delivered.pop_front()
+    }
+
+    pub fn get_delivered_count(&self) -> u64 {
+        self.lock_state().delivered_messages
+    }
+
+    /// Gets count of data frames, which were received more than once.
+    pub fn get_duplicate_count(&self) -> u64 {
+        self.lock_state().duplicate_frames
+    }
+
+    /// Gets count of data frames, which came ahead of the expected one and could not be kept,
+    /// like every such frame in stop-and-wait and go-back-N.
+    pub fn get_discarded_count(&self) -> u64 {
+        self.lock_state().discarded_frames
+    }
+
+    /// Gets count of frames dropped as corrupted.
+    pub fn get_corrupted_count(&self) -> u64 {
+        self.lock_state().decoder.get_dropped_frame_count()
+    }
+}
+
+impl ReceiverState {
+    fn deliver(&mut self, payload: Vec<u8>) {
+        self.delivered.push_back(payload);
+        self.delivered_messages += 1;
+        self.expected_sequence = self.expected_sequence.wrapping_add(1);
+    }
+
+    /// Counts the frame, which can not be kept, as a duplicate if it is behind
+    /// the expected one, or as discarded if it is ahead.
+    fn reject(&mut self, offset: u16) {
+        match offset >= SEQUENCE_HALF_RANGE {
+            true => self.duplicate_frames += 1,
+            false => self.discarded_frames += 1,
+        }
+    }
+
+    /// Gives sequence number to acknowledge.
+    fn receive_data(&mut self, sequence: u16, payload: &[u8]) -> u16 {
+        let offset = sequence.wrapping_sub(self.expected_sequence);
+        match self.mode {
+            ArqMode::StopAndWait | ArqMode::GoBackN { .. } => {
+                match offset {
+                    0 => self.deliver(payload.to_vec()),
+                    _ => self.reject(offset),
+                }
+                self.expected_sequence
+            }
+            ArqMode::SelectiveRepeat { window } => {
+                if offset < window {
+                    if self
+                        .out_of_order
+                        .insert(sequence, payload.to_vec())
+                        .is_some()
+                    {
+                        self.duplicate_frames += 1;
+                    }
+                    while let Some(payload) = self.out_of_order.remove(&self.expected_sequence) {
+                        self.deliver(payload);
+                    }
+                } else {
+                    // Frame behind the window is the one, acknowledgement of which was lost.
+                    self.reject(offset);
+                }
+                sequence
+            }
+        }
+    }
+}
+
+impl Clone for ArqReceiver {
+    fn clone(&self) -> Self {
+        ArqReceiver {
+            state: Arc::clone(&self.state),
+        }
+    }
+}
+
+impl Application for ArqReceiver {
+    fn update(&mut self, modem: &mut WirelessModemFake, _now: u64) {
+        let mut state = self.lock_state();
+        while let Some(byte) = modem.get_from_tx_pin() {
+            if let Some(frame) = state.decoder.push(byte) {
+                if let Some((DATA_FRAME, sequence, payload)) = parse_frame(&frame) {
+                    let acknowledgement = state.receive_data(sequence, payload);
+                    match state.mode {
+                        // Cumulative acknowledgement covers all the previous ones.
+                        ArqMode::StopAndWait | ArqMode::GoBackN { .. } => {
+                            state.pending_acknowledgements = vec![acknowledgement]
+                        }
+                        ArqMode::SelectiveRepeat { .. } => {
+                            state.pending_acknowledgements.push(acknowledgement)
+                        }
+                    }
+                }
+            }
+        }
+
+        if modem.is_busy() || modem.is_receiving() {
+            return;
+        }
+        for acknowledgement in std::mem::take(&mut state.pending_acknowledgements) {
+            write_frame(modem, encode_frame(ACK_FRAME, acknowledgement, &[]));
+        }
+    }
+
+    /// Out of order frames are lost with the state of the device.
+    fn reboot(&mut self) {
+        let mut state = self.lock_state();
+        state.out_of_order.clear();
+        state.pending_acknowledgements.clear();
+        state.decoder = FrameDecoder::new(ARQ_FRAMING);
+    }
+}
+
+#[cfg(test)]
+mod arq_tests {
+    use crate::{
+        ArqMode, ArqReceiver, ArqSender, IODriverSimulator, NetworkSimulator, WirelessModemFake,
+    };
+
+    /// Runs the transfer over the ether with a jammer, which corrupts a frame
+    /// every `jamming_period` ms. Gives the time the transfer ended at.
+    fn run_transfer(
+        mode: ArqMode,
+        message_count: u8,
+        jamming_period: u64,
+    ) -> (ArqSender, ArqReceiver, u64) {
+        let simulator = NetworkSimulator::new(1);
+        simulator.create_ether("air");
+        let mut ether = simulator.get_ether("air").unwrap();
+        let modems: Vec<WirelessModemFake> = ["sender", "receiver", "jammer"]
+            .iter()
+            .map(|name| {
+                let modem = WirelessModemFake::new(name);
+                ether.register_driver(modem.clone());
+                modem
+            })
+            .collect();
+
+        let sender = ArqSender::new(mode, 60, 0);
+        let receiver = ArqReceiver::new(mode);
+        simulator.register_application(modems[0].clone(), sender.clone());
+        simulator.register_application(modems[1].clone(), receiver.clone());
+        simulator.register_application(
+            modems[2].clone(),
+            move |modem: &mut WirelessModemFake, now: u64| {
+                while modem.get_from_tx_pin().is_some() {}
+                if now.is_multiple_of(jamming_period) {
+                    modem.put_to_rx_pin(0x55);
+                }
+            },
+        );
+
+        for index in 0..message_count {
+            sender.send(&[index; 16]);
+        }
+        for _ in 0..20_000 {
+            if sender.is_idle() {
+                break;
+            }
+            simulator.tick();
+        }
+        (sender, receiver, simulator.get_virtual_time_ms())
+    }
+
+    fn assert_delivered_in_order(receiver: &ArqReceiver, message_count: u8) {
+        for index in 0..message_count {
+            assert_eq!(receiver.receive(), Some(vec![index; 16]));
+        }
+        assert_eq!(receiver.receive(), None);
+    }
+
+    #[test]
+    fn test_stop_and_wait_recovers_from_corruption() {
+        let (sender, receiver, _) = run_transfer(ArqMode::StopAndWait, 20, 97);
+        assert!(sender.is_idle());
+        assert!(sender.get_stats().retransmitted_frames > 0);
+        assert!(receiver.get_corrupted_count() > 0);
+        assert_delivered_in_order(&receiver, 20);
+    }
+
+    #[test]
+    fn test_sliding_windows_deliver_in_order() {
+        for mode in [
+            ArqMode::GoBackN { window: 4 },
+            ArqMode::SelectiveRepeat { window: 4 },
+        ] {
+            let (sender, receiver, now) = run_transfer(mode, 40, 97);
+            assert!(sender.is_idle());
+            assert_eq!(sender.get_stats().acknowledged_messages, 40);
+            assert!(sender.get_stats().get_throughput_bps(now).unwrap() > 0.0);
+            assert_delivered_in_order(&receiver, 40);
+        }
+    }
+
+    #[test]
+    fn test_timeout_jitter_escapes_periodic_jamming() {
+        // Jammer period equals the timeout, so without jitter every retransmission is jammed.
+        let (sender, receiver, now) = run_transfer(ArqMode::StopAndWait, 20, 60);
+        assert!(sender.is_idle());
+        assert_delivered_in_order(&receiver, 20);
+
+        // Time without acknowledgements lowers the throughput.
+        let stats = sender.get_stats();
+        let throughput = stats.get_throughput_bps(now).unwrap();
+        assert!(stats.get_throughput_bps(2 * now).unwrap() < throughput);
+    }
+
+    #[test]
+    fn test_frames_ahead_are_not_duplicates() {
+        for mode in [ArqMode::StopAndWait, ArqMode::GoBackN { window: 4 }] {
+            let receiver = ArqReceiver::new(mode);
+            receiver.lock_state().receive_data(1, &[1]);
+            receiver.lock_state().receive_data(0, &[0]);
+            receiver.lock_state().receive_data(0, &[0]);
+            assert_eq!(receiver.get_discarded_count(), 1);
+            assert_eq!(receiver.get_duplicate_count(), 1);
+            assert_eq!(receiver.receive(), Some(vec![0]));
+            assert_eq!(receiver.receive(), None);
+        }
+
+        let receiver = ArqReceiver::new(ArqMode::SelectiveRepeat { window: 4 });
+        receiver.lock_state().receive_data(4, &[4]);
+        receiver.lock_state().receive_data(0, &[0]);
+        receiver.lock_state().receive_data(0, &[0]);
+        assert_eq!(receiver.get_discarded_count(), 1);
+        assert_eq!(receiver.get_duplicate_count(), 1);
+    }
+}
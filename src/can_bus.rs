@@ -0,0 +1,290 @@
+use std::sync::{Arc, Mutex};
+
+use crate::{CanController, CanFrame, MediumSimulator};
+
+struct BusState {
+    injected_errors: u32,
+    transmitted_frame_count: u64,
+    error_frame_count: u64,
+}
+
+/// CAN bus, where one frame is transmitted per tick.
+/// Controllers with pending frames arbitrate bitwise, so the lowest identifier wins,
+/// and the losers retry in next ticks. Errors are signalled by error frames,
+/// which make the transmitter retry and increase error counters of the controllers.
+pub struct CanBus {
+    name: String,
+    controllers: Arc<Mutex<Vec<CanController>>>,
+    state: Arc<Mutex<BusState>>,
+}
+
+impl CanBus {
+    pub fn new(name: &str) -> Self {
+        CanBus {
+            name: String::from(name),
+            controllers: Arc::new(Mutex::new(Vec::new())),
+            state: Arc::new(Mutex::new(BusState {
+                injected_errors: 0,
+                transmitted_frame_count: 0,
+                error_frame_count: 0,
+            })),
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Connects the controller to the bus.
+    pub fn register_controller(&mut self, controller: CanController) {
+        let mut controllers = self
+            .controllers
+            .lock()
+            .expect("Fail to get lock on controllers");
+        controllers.push(controller);
+    }
+
+    /// Disconnects all the controllers with the name from the bus.
+    pub fn unregister_controller(&mut self, name: &str) {
+        let mut controllers = self
+            .controllers
+            .lock()
+            .expect("Fail to get lock on controllers");
+        controllers.retain(|controller| controller.get_name() != name);
+    }
+
+    /// Gets connected controller.
+    pub fn get_controller(&self, name: &str) -> Option<CanController> {
+        let controllers = self
+            .controllers
+            .lock()
+            .expect("Fail to get lock on controllers");
+        controllers
+            .iter()
+            .find(|controller| controller.get_name() == name)
+            .cloned()
+    }
+
+    /// Corrupts next `count` transmitted frames, like electrical disturbance would do.
+    /// ```
+    /// use proto_lab::{CanBus, CanController, CanErrorState, CanFrame, MediumSimulator};
+    ///
+    /// let mut bus = CanBus::new("bus");
+    /// let sender = CanController::new("sender");
+    /// let receiver = CanController::new("receiver");
+    /// bus.register_controller(sender.clone());
+    /// bus.register_controller(receiver.clone());
+    ///
+    /// sender.transmit(CanFrame::new(0x10, &[1, 2]));
+    /// bus.inject_errors(17);
+    /// for _ in 0..17 {
+    ///     bus.start_tick();
+    ///     bus.simulate();
+    ///     bus.end_tick();
+    /// }
+    ///
+    /// assert_eq!(sender.get_transmit_error_counter(), 136);
+    /// assert_eq!(sender.get_error_state(), CanErrorState::ErrorPassive);
+    /// assert_eq!(receiver.get_receive_error_counter(), 17);
+    /// assert_eq!(bus.get_error_frame_count(), 17);
+    ///
+    /// bus.start_tick();
+    /// bus.simulate();
+    /// bus.end_tick();
+    /// assert_eq!(receiver.receive(), Some(CanFrame::new(0x10, &[1, 2])));
+    /// ```
+    pub fn inject_errors(&self, count: u32) {
+        self.state
+            .lock()
+            .expect("Fail to get lock on bus state")
+            .injected_errors += count;
+    }
+
+    /// Gets count of frames transmitted without errors.
+    pub fn get_transmitted_frame_count(&self) -> u64 {
+        self.state
+            .lock()
+            .expect("Fail to get lock on bus state")
+            .transmitted_frame_count
+    }
+
+    /// Gets count of error frames signalled on the bus.
+    pub fn get_error_frame_count(&self) -> u64 {
+        self.state
+            .lock()
+            .expect("Fail to get lock on bus state")
+            .error_frame_count
+    }
+}
+
+impl MediumSimulator for CanBus {
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Frame is arbitrated and delivered within `simulate`.
+    fn start_tick(&self) {}
+
+    fn simulate(&self) {
+        let controllers = self
+            .controllers
+            .lock()
+            .expect("Fail to get lock on controllers");
+
+        let candidates: Vec<Option<CanFrame>> = controllers
+            .iter()
+            .map(|controller| controller.get_pending_frame())
+            .collect();
+
+        let winning_key = match candidates
+            .iter()
+            .flatten()
+            .map(|frame| frame.get_arbitration_key())
+            .min()
+        {
+            Some(key) => key,
+            None => return,
+        };
+
+        let winners: Vec<usize> = candidates
+            .iter()
+            .enumerate()
+            .filter(|(_, frame)| {
+                matches!(frame, Some(frame) if frame.get_arbitration_key() == winning_key)
+            })
+            .map(|(index, _)| index)
+            .collect();
+
+        let frame = candidates[winners[0]]
+            .clone()
+            .expect("Winner shall have a frame");
+
+        let mut state = self.state.lock().expect("Fail to get lock on bus state");
+
+        // Several transmitters with the same identifier but different data
+        // notice bit error in the data field.
+        let mut error = winners
+            .iter()
+            .any(|index| candidates[*index].as_ref() != Some(&frame));
+
+        if state.injected_errors > 0 {
+            state.injected_errors -= 1;
+            error = true;
+        }
+
+        let receivers: Vec<&CanController> = controllers
+            .iter()
+            .enumerate()
+            .filter(|(index, controller)| !winners.contains(index) && !controller.is_bus_off())
+            .map(|(_, controller)| controller)
+            .collect();
+
+        for (index, candidate) in candidates.iter().enumerate() {
+            if candidate.is_some() && !winners.contains(&index) {
+                controllers[index].on_arbitration_lost();
+            }
+        }
+
+        if error {
+            state.error_frame_count += 1;
+            for index in winners.iter() {
+                controllers[*index].on_transmit_error();
+            }
+            for receiver in receivers.iter() {
+                receiver.on_receive_error();
+            }
+        } else if receivers.is_empty() {
+            state.error_frame_count += 1;
+            for index in winners.iter() {
+                controllers[*index].on_acknowledgement_error();
+            }
+        } else {
+            state.transmitted_frame_count += 1;
+            for index in winners.iter() {
+                controllers[*index].on_transmit_success(&frame);
+            }
+            for receiver in receivers.iter() {
+                receiver.on_receive_success(&frame);
+            }
+        }
+    }
+
+    /// Frame is arbitrated and delivered within `simulate`.
+    fn end_tick(&self) {}
+}
+
+impl Clone for CanBus {
+    fn clone(&self) -> Self {
+        CanBus {
+            name: self.name.clone(),
+            controllers: Arc::clone(&self.controllers),
+            state: Arc::clone(&self.state),
+        }
+    }
+}
+
+#[cfg(test)]
+mod can_bus_tests {
+    use crate::{CanBus, CanController, CanErrorState, CanFrame, NetworkSimulator};
+
+    fn create_bus(names: &[&str]) -> (NetworkSimulator, CanBus, Vec<CanController>) {
+        let simulator = NetworkSimulator::new(1);
+        let mut bus = CanBus::new("bus");
+        let controllers: Vec<CanController> =
+            names.iter().map(|name| CanController::new(name)).collect();
+        for controller in controllers.iter() {
+            bus.register_controller(controller.clone());
+        }
+        simulator.add_medium(bus.clone());
+        (simulator, bus, controllers)
+    }
+
+    #[test]
+    fn test_lowest_id_wins_and_losers_retry() {
+        let (simulator, _bus, controllers) = create_bus(&["a", "b", "listener"]);
+        controllers[0].transmit(CanFrame::new(0x300, &[0xA]));
+        controllers[1].transmit(CanFrame::new(0x200, &[0xB]));
+
+        simulator.run_ticks(2);
+
+        assert_eq!(controllers[2].receive().unwrap().get_id(), 0x200);
+        assert_eq!(controllers[2].receive().unwrap().get_id(), 0x300);
+        assert_eq!(controllers[0].get_arbitration_lost_count(), 1);
+        assert_eq!(controllers[0].receive().unwrap().get_id(), 0x200);
+        assert_eq!(controllers[0].get_pending_count(), 0);
+    }
+
+    #[test]
+    fn test_bus_off_and_reset() {
+        let (simulator, bus, controllers) = create_bus(&["a", "b"]);
+        controllers[0].transmit(CanFrame::new(0x1, &[]));
+        bus.inject_errors(32);
+
+        simulator.run_ticks(32);
+        assert_eq!(controllers[0].get_error_state(), CanErrorState::BusOff);
+        assert_eq!(controllers[0].get_pending_count(), 1);
+
+        simulator.run_ticks(1);
+        assert_eq!(controllers[1].receive(), None);
+
+        controllers[0].reset();
+        simulator.run_ticks(1);
+        assert_eq!(controllers[0].get_error_state(), CanErrorState::ErrorActive);
+        assert_eq!(controllers[1].receive(), Some(CanFrame::new(0x1, &[])));
+    }
+
+    #[test]
+    fn test_lonely_transmitter_stays_error_passive() {
+        let (simulator, bus, controllers) = create_bus(&["alone"]);
+        controllers[0].transmit(CanFrame::new(0x1, &[]));
+
+        simulator.run_ticks(100);
+
+        assert_eq!(
+            controllers[0].get_error_state(),
+            CanErrorState::ErrorPassive
+        );
+        assert_eq!(controllers[0].get_transmit_error_counter(), 128);
+        assert_eq!(bus.get_error_frame_count(), 100);
+    }
+}
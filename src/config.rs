@@ -0,0 +1,266 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    path::Path,
+};
+
+use serde::Deserialize;
+
+use crate::{NetworkSimulator, WirelessModemFake};
+
+/// Declarative description of the simulated network.
+/// ```toml
+/// ms_per_tick = 1
+///
+/// [[ethers]]
+/// name = "1"
+///
+/// [[devices]]
+/// name = "a"
+/// ethers = ["1"]
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NetworkConfig {
+    pub ms_per_tick: u64,
+    #[serde(default)]
+    pub ethers: Vec<EtherConfig>,
+    #[serde(default)]
+    pub devices: Vec<DeviceConfig>,
+}
+
+/// Declarative description of a single ether.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EtherConfig {
+    pub name: String,
+}
+
+/// Declarative description of a single device and the ethers it is registered in.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeviceConfig {
+    pub name: String,
+    #[serde(default)]
+    pub ethers: Vec<String>,
+}
+
+/// Format of the configuration text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Toml,
+    Json,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(std::io::Error),
+    UnknownFormat(String),
+    Parse(String),
+    DuplicateEther(String),
+    DuplicateDevice(String),
+    DanglingEther { device: String, ether: String },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(error) => write!(f, "Fail to read config: {}", error),
+            ConfigError::UnknownFormat(path) => write!(
+                f,
+                "Unknown config format of {}. Expected .toml or .json extension",
+                path
+            ),
+            ConfigError::Parse(error) => write!(f, "Fail to parse config: {}", error),
+            ConfigError::DuplicateEther(name) => write!(f, "Ether {} is declared twice", name),
+            ConfigError::DuplicateDevice(name) => {
+                write!(f, "Device {} is declared twice", name)
+            }
+            ConfigError::DanglingEther { device, ether } => write!(
+                f,
+                "Device {} refers to ether {} which is not declared",
+                device, ether
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl NetworkConfig {
+    /// Parses the configuration text.
+    pub fn parse(text: &str, format: ConfigFormat) -> Result<Self, ConfigError> {
+        match format {
+            ConfigFormat::Toml => {
+                toml::from_str(text).map_err(|error| ConfigError::Parse(error.to_string()))
+            }
+            ConfigFormat::Json => {
+                serde_json::from_str(text).map_err(|error| ConfigError::Parse(error.to_string()))
+            }
+        }
+    }
+
+    /// Checks that names are unique and that every device refers only to declared ethers.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut ether_names = HashSet::new();
+        for ether in self.ethers.iter() {
+            if !ether_names.insert(ether.name.as_str()) {
+                return Err(ConfigError::DuplicateEther(ether.name.clone()));
+            }
+        }
+
+        let mut device_names = HashSet::new();
+        for device in self.devices.iter() {
+            if !device_names.insert(device.name.as_str()) {
+                return Err(ConfigError::DuplicateDevice(device.name.clone()));
+            }
+            for ether in device.ethers.iter() {
+                if !ether_names.contains(ether.as_str()) {
+                    return Err(ConfigError::DanglingEther {
+                        device: device.name.clone(),
+                        ether: ether.clone(),
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl NetworkSimulator {
+    /// Builds the simulator from TOML or JSON file. Format is chosen by file extension.
+    /// Returns the simulator together with all the declared devices by their names.
+    pub fn from_config(
+        path: impl AsRef<Path>,
+    ) -> Result<(NetworkSimulator, HashMap<String, WirelessModemFake>), ConfigError> {
+        let path = path.as_ref();
+
+        let format = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => ConfigFormat::Toml,
+            Some("json") => ConfigFormat::Json,
+            _ => return Err(ConfigError::UnknownFormat(path.display().to_string())),
+        };
+
+        let text = std::fs::read_to_string(path).map_err(ConfigError::Io)?;
+
+        NetworkSimulator::from_config_str(&text, format)
+    }
+
+    /// Builds the simulator from configuration text.
+    /// ```
+    /// use proto_lab::{ConfigFormat, NetworkSimulator};
+    ///
+    /// let (simulator, devices) = NetworkSimulator::from_config_str(
+    ///     r#"
+    ///     ms_per_tick = 1
+    ///
+    ///     [[ethers]]
+    ///     name = "1"
+    ///
+    ///     [[devices]]
+    ///     name = "a"
+    ///     ethers = ["1"]
+    ///     "#,
+    ///     ConfigFormat::Toml,
+    /// )
+    /// .unwrap();
+    ///
+    /// assert!(simulator.get_ether("1").unwrap().get_driver("a").is_some());
+    /// assert!(devices.contains_key("a"));
+    /// ```
+    pub fn from_config_str(
+        text: &str,
+        format: ConfigFormat,
+    ) -> Result<(NetworkSimulator, HashMap<String, WirelessModemFake>), ConfigError> {
+        NetworkSimulator::from_network_config(&NetworkConfig::parse(text, format)?)
+    }
+
+    /// Builds the simulator from already parsed configuration.
+    pub fn from_network_config(
+        config: &NetworkConfig,
+    ) -> Result<(NetworkSimulator, HashMap<String, WirelessModemFake>), ConfigError> {
+        config.validate()?;
+
+        let simulator = NetworkSimulator::new(config.ms_per_tick);
+        for ether in config.ethers.iter() {
+            simulator.create_ether(&ether.name);
+        }
+
+        let mut devices = HashMap::new();
+        for device_config in config.devices.iter() {
+            let device = WirelessModemFake::new(&device_config.name);
+            for ether_name in device_config.ethers.iter() {
+                simulator
+                    .get_ether(ether_name)
+                    .expect("Ether is validated to exist")
+                    .register_driver(device.clone());
+            }
+            devices.insert(device_config.name.clone(), device);
+        }
+
+        Ok((simulator, devices))
+    }
+}
+
+#[cfg(test)]
+mod config_tests {
+    use super::*;
+
+    #[test]
+    fn test_json_config() {
+        let (simulator, devices) = NetworkSimulator::from_config_str(
+            r#"{
+                "ms_per_tick": 1,
+                "ethers": [{ "name": "1" }, { "name": "2" }],
+                "devices": [
+                    { "name": "a", "ethers": ["1"] },
+                    { "name": "b", "ethers": ["1", "2"] }
+                ]
+            }"#,
+            ConfigFormat::Json,
+        )
+        .unwrap();
+
+        assert_eq!(devices.len(), 2);
+        assert!(simulator.get_ether("2").unwrap().get_driver("b").is_some());
+        assert!(simulator.get_ether("2").unwrap().get_driver("a").is_none());
+    }
+
+    #[test]
+    fn test_dangling_ether_is_rejected() {
+        let result = NetworkSimulator::from_config_str(
+            r#"
+            ms_per_tick = 1
+
+            [[devices]]
+            name = "a"
+            ethers = ["missing"]
+            "#,
+            ConfigFormat::Toml,
+        );
+
+        assert!(matches!(
+            result,
+            Err(ConfigError::DanglingEther { ref device, ref ether }) if device == "a" && ether == "missing"
+        ));
+    }
+
+    #[test]
+    fn test_duplicate_device_is_rejected() {
+        let result = NetworkSimulator::from_config_str(
+            r#"
+            ms_per_tick = 1
+
+            [[devices]]
+            name = "a"
+
+            [[devices]]
+            name = "a"
+            "#,
+            ConfigFormat::Toml,
+        );
+
+        assert!(matches!(result, Err(ConfigError::DuplicateDevice(ref name)) if name == "a"));
+    }
+}
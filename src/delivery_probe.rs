@@ -0,0 +1,384 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use crate::{IODriverSimulator, WirelessModemFake};
+
+/// Length of the tagged message: `<`, message id as 8 hex digits, checksum as 2 hex digits, `>`.
+pub const TAGGED_MESSAGE_LENGTH: usize = 12;
+
+fn checksum(id: u32) -> u8 {
+    id.to_be_bytes()
+        .iter()
+        .fold(0u8, |checksum, byte| checksum.wrapping_add(*byte))
+}
+
+fn encode_tag(id: u32) -> Vec<u8> {
+    format!("<{:08x}{:02x}>", id, checksum(id)).into_bytes()
+}
+
+fn decode_tag(window: &VecDeque<u8>) -> Option<u32> {
+    if window.len() != TAGGED_MESSAGE_LENGTH
+        || window.front() != Some(&b'<')
+        || window.back() != Some(&b'>')
+    {
+        return None;
+    }
+    let body: Vec<u8> = window
+        .iter()
+        .skip(1)
+        .take(TAGGED_MESSAGE_LENGTH - 2)
+        .copied()
+        .collect();
+    let body = std::str::from_utf8(&body).ok()?;
+    let id = u32::from_str_radix(&body[..8], 16).ok()?;
+    let sent_checksum = u8::from_str_radix(&body[8..], 16).ok()?;
+    (checksum(id) == sent_checksum).then_some(id)
+}
+
+/// Fate of a single tagged message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageRecord {
+    pub id: u32,
+    pub destination: String,
+    pub sent_at_tick: u64,
+    /// Tick of the first detection on the TX pin of the destination.
+    pub delivered_at_tick: Option<u64>,
+    /// Count of detections on the TX pin of the destination, including the first one.
+    pub delivery_count: u64,
+    /// Message was first detected after a message sent later to the same destination.
+    pub is_reordered: bool,
+}
+
+impl MessageRecord {
+    pub fn get_latency_ticks(&self) -> Option<u64> {
+        self.delivered_at_tick
+            .map(|delivered_at_tick| delivered_at_tick - self.sent_at_tick)
+    }
+}
+
+/// Summary of all the tagged messages sent so far.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct DeliveryReport {
+    pub sent_messages: u64,
+    pub delivered_messages: u64,
+    /// Count of detections beyond the first one of each message.
+    pub duplicate_messages: u64,
+    pub reordered_messages: u64,
+    pub average_latency_ticks: Option<f64>,
+    pub max_latency_ticks: Option<u64>,
+}
+
+impl DeliveryReport {
+    /// Gets share of the sent messages, which reached their destinations.
+    pub fn get_delivery_ratio(&self) -> f64 {
+        match self.sent_messages {
+            0 => 0.0,
+            sent_messages => self.delivered_messages as f64 / sent_messages as f64,
+        }
+    }
+}
+
+struct WatchedDestination {
+    name: String,
+    tap: Arc<Mutex<VecDeque<u8>>>,
+    window: VecDeque<u8>,
+    last_delivered_id: Option<u32>,
+}
+
+struct ProbeState {
+    /// Tick count of the simulator, which the probe is attached to.
+    tick_count: Option<Arc<Mutex<u64>>>,
+    messages: Vec<MessageRecord>,
+    destinations: Vec<WatchedDestination>,
+}
+
+/// Measures end-to-end latency and delivery of tagged messages.
+/// Tagged messages are made by the probe for a destination modem, and are written into
+/// the RX pin of the source modem with `inject`, or handed to the protocol code of the source
+/// to be relayed. The probe detects them among bytes put out on the TX pin of the destination,
+/// while the protocol code still reads those bytes as usual.
+///
+/// Tagged messages are printable ASCII, so they pass SLIP and HDLC byte stuffing unchanged.
+/// Protocol code may carry them within longer payloads.
+///
+/// Attach a clone of the probe to the simulator with `NetworkSimulator::attach_probe`
+/// before creating messages, so the probe scans the destinations after every tick
+/// and stamps messages with ticks of the simulator. Keep the other one to read results.
+/// ```
+/// use proto_lab::{DeliveryProbe, NetworkSimulator, WirelessModemFake, TAGGED_MESSAGE_LENGTH};
+///
+/// let simulator = NetworkSimulator::new(1);
+/// simulator.create_ether("air");
+/// let mut ether = simulator.get_ether("air").unwrap();
+/// let source = WirelessModemFake::new("source");
+/// let destination = WirelessModemFake::new("destination");
+/// ether.register_driver(source.clone());
+/// ether.register_driver(destination.clone());
+///
+/// let probe = DeliveryProbe::new();
+/// simulator.attach_probe(probe.clone());
+///
+/// probe.inject(&source, &destination);
+/// simulator.run_ticks(20);
+///
+/// let report = probe.get_report();
+/// assert_eq!(report.get_delivery_ratio(), 1.0);
+/// assert_eq!(report.max_latency_ticks, Some(TAGGED_MESSAGE_LENGTH as u64));
+/// ```
+pub struct DeliveryProbe {
+    state: Arc<Mutex<ProbeState>>,
+}
+
+impl DeliveryProbe {
+    pub fn new() -> Self {
+        DeliveryProbe {
+            state: Arc::new(Mutex::new(ProbeState {
+                tick_count: None,
+                messages: Vec::new(),
+                destinations: Vec::new(),
+            })),
+        }
+    }
+
+    fn lock_state(&self) -> std::sync::MutexGuard<'_, ProbeState> {
+        self.state
+            .lock()
+            .expect("Fail to lock delivery probe state")
+    }
+
+    /// Makes the probe take ticks from the tick count of the simulator.
+    pub(crate) fn attach(&self, tick_count: Arc<Mutex<u64>>) {
+        let mut state = self.lock_state();
+        if state.tick_count.is_some() {
+            panic!("Delivery probe is already attached to a simulator")
+        }
+        state.tick_count = Some(tick_count);
+    }
+
+    /// Creates the tagged message for the destination, sent at the current tick of the simulator.
+    /// The destination is watched from now on.
+    /// Panics if the probe is not attached to a simulator, as the message would never be detected.
+    pub fn create_message(&self, destination: &WirelessModemFake) -> Vec<u8> {
+        let Some(tick_count) = self.lock_state().tick_count.clone() else {
+            panic!("Delivery probe is not attached to a simulator")
+        };
+        let sent_at_tick = *tick_count.lock().expect("Fail to get lock on tick count");
+        let mut state = self.lock_state();
+
+        let destination_name = destination.get_name();
+        if !state
+            .destinations
+            .iter()
+            .any(|watched| watched.name == destination_name)
+        {
+            let tap = Arc::new(Mutex::new(VecDeque::new()));
+            destination.add_tx_pin_tap(Arc::clone(&tap));
+            state.destinations.push(WatchedDestination {
+                name: String::from(destination_name),
+                tap,
+                window: VecDeque::with_capacity(TAGGED_MESSAGE_LENGTH),
+                last_delivered_id: None,
+            });
+        }
+
+        let id = state.messages.len() as u32;
+        state.messages.push(MessageRecord {
+            id,
+            destination: String::from(destination_name),
+            sent_at_tick,
+            delivered_at_tick: None,
+            delivery_count: 0,
+            is_reordered: false,
+        });
+        encode_tag(id)
+    }
+
+    /// Scans bytes, which were put out on TX pins of the destinations during the tick.
+    /// It is called by the simulator after ending the tick, so the bytes of this tick are there.
+    pub(crate) fn scan(&self, tick: u64) {
+        let mut state = self.lock_state();
+
+        for destination_index in 0..state.destinations.len() {
+            let bytes: Vec<u8> = state.destinations[destination_index]
+                .tap
+                .lock()
+                .expect("Fail to lock TX pin tap")
+                .drain(..)
+                .collect();
+
+            for byte in bytes {
+                let window = &mut state.destinations[destination_index].window;
+                if window.len() == TAGGED_MESSAGE_LENGTH {
+                    window.pop_front();
+                }
+                window.push_back(byte);
+
+                if let Some(id) = decode_tag(window) {
+                    window.clear();
+                    state.detect(destination_index, id, tick);
+                }
+            }
+        }
+    }
+
+    /// Writes the tagged message for the destination into the RX pin of the source.
+    pub fn inject(&self, source: &WirelessModemFake, destination: &WirelessModemFake) {
+        for byte in self.create_message(destination) {
+            source.put_to_rx_pin(byte);
+        }
+    }
+
+    /// Gets records of all the messages in order of sending.
+    pub fn get_messages(&self) -> Vec<MessageRecord> {
+        self.lock_state().messages.clone()
+    }
+
+    pub fn get_report(&self) -> DeliveryReport {
+        let state = self.lock_state();
+
+        let latencies: Vec<u64> = state
+            .messages
+            .iter()
+            .filter_map(MessageRecord::get_latency_ticks)
+            .collect();
+        DeliveryReport {
+            sent_messages: state.messages.len() as u64,
+            delivered_messages: latencies.len() as u64,
+            duplicate_messages: state
+                .messages
+                .iter()
+                .map(|message| message.delivery_count.saturating_sub(1))
+                .sum(),
+            reordered_messages: state
+                .messages
+                .iter()
+                .filter(|message| message.is_reordered)
+                .count() as u64,
+            average_latency_ticks: match latencies.len() {
+                0 => None,
+                count => Some(latencies.iter().sum::<u64>() as f64 / count as f64),
+            },
+            max_latency_ticks: latencies.iter().max().copied(),
+        }
+    }
+}
+
+impl ProbeState {
+    fn detect(&mut self, destination_index: usize, id: u32, tick: u64) {
+        let destination = &mut self.destinations[destination_index];
+        // Tags of other destinations may be heard on the way, like by relays.
+        let Some(message) = self
+            .messages
+            .get_mut(id as usize)
+            .filter(|message| message.destination == destination.name)
+        else {
+            return;
+        };
+
+        message.delivery_count += 1;
+        if message.delivered_at_tick.is_none() {
+            message.delivered_at_tick = Some(tick);
+            if destination
+                .last_delivered_id
+                .is_some_and(|last_delivered_id| last_delivered_id > id)
+            {
+                message.is_reordered = true;
+            }
+            destination.last_delivered_id = destination.last_delivered_id.max(Some(id));
+        }
+    }
+}
+
+impl Clone for DeliveryProbe {
+    fn clone(&self) -> Self {
+        DeliveryProbe {
+            state: Arc::clone(&self.state),
+        }
+    }
+}
+
+impl Default for DeliveryProbe {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod delivery_probe_tests {
+    use super::*;
+    use crate::NetworkSimulator;
+
+    #[test]
+    fn test_tag_round_trip() {
+        for id in [0, 1, 0xDEAD_BEEF, u32::MAX] {
+            assert_eq!(decode_tag(&encode_tag(id).into()), Some(id));
+        }
+        let mut corrupted = encode_tag(7);
+        corrupted[8] = b'8';
+        assert_eq!(decode_tag(&corrupted.into()), None);
+    }
+
+    #[test]
+    fn test_multi_hop_relay_with_duplicates_and_reordering() {
+        // source -- near -- relay -- far -- destination
+        let simulator = NetworkSimulator::new(1);
+        simulator.create_ether("near");
+        simulator.create_ether("far");
+        let source = WirelessModemFake::new("source");
+        let relay = WirelessModemFake::new("relay");
+        let destination = WirelessModemFake::new("destination");
+        let mut near = simulator.get_ether("near").unwrap();
+        let mut far = simulator.get_ether("far").unwrap();
+        near.register_driver(source.clone());
+        near.register_driver(relay.clone());
+        far.register_driver(relay.clone());
+        far.register_driver(destination.clone());
+
+        let probe = DeliveryProbe::new();
+        simulator.attach_probe(probe.clone());
+
+        // Relay repeats everything it hears twice, once the channel is quiet.
+        let mut heard = Vec::new();
+        simulator.register_application(relay, move |modem: &mut WirelessModemFake, _now| {
+            while let Some(byte) = modem.get_from_tx_pin() {
+                heard.push(byte);
+            }
+            if !heard.is_empty() && !modem.is_receiving() && !modem.is_busy() {
+                modem.write(&heard).unwrap();
+                modem.write(&heard).unwrap();
+                heard.clear();
+            }
+        });
+        simulator.register_application(
+            destination.clone(),
+            |modem: &mut WirelessModemFake, _now| {
+                let mut buf = [0u8; 64];
+                while modem.read(&mut buf).unwrap() > 0 {}
+            },
+        );
+
+        let first = probe.create_message(&destination);
+        let second = probe.create_message(&destination);
+        source.write(&second).unwrap();
+        source.write(&first).unwrap();
+        simulator.run_ticks(200);
+
+        let report = probe.get_report();
+        assert_eq!(report.get_delivery_ratio(), 1.0);
+        assert_eq!(report.duplicate_messages, 2);
+        assert_eq!(report.reordered_messages, 1);
+
+        let messages = probe.get_messages();
+        assert!(messages[0].is_reordered);
+        assert!(messages[0].get_latency_ticks() > messages[1].get_latency_ticks());
+        assert!(messages[1].get_latency_ticks().unwrap() > 2 * TAGGED_MESSAGE_LENGTH as u64);
+    }
+
+    #[test]
+    #[should_panic(expected = "Delivery probe is not attached to a simulator")]
+    fn test_detached_probe_refuses_to_create_messages() {
+        DeliveryProbe::new().create_message(&WirelessModemFake::new("destination"));
+    }
+}
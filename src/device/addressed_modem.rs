@@ -0,0 +1,381 @@
+use std::collections::VecDeque;
+
+use super::{IODriverSimulator, WirelessModemFake};
+
+/// Address, which reaches every module on the channel, and which makes a module
+/// hear every packet on its channel.
+pub const BROADCAST_ADDRESS: u16 = 0xFFFF;
+
+/// Count of bytes ahead of the payload in the air: address, channel and length.
+const AIR_HEADER_LENGTH: usize = 4;
+
+/// Length of the frame check sequence of the packet in the air.
+const AIR_CRC_LENGTH: usize = 2;
+
+/// Calculates CRC-16/X.25 of the packet in the air.
+fn air_crc(data: &[u8]) -> [u8; AIR_CRC_LENGTH] {
+    let mut crc: u16 = 0xFFFF;
+    for byte in data {
+        crc ^= *byte as u16;
+        for _ in 0..8 {
+            crc = match crc & 1 {
+                1 => (crc >> 1) ^ 0x8408,
+                _ => crc >> 1,
+            };
+        }
+    }
+    (!crc).to_le_bytes()
+}
+
+/// Transmission mode of the modem, like the one of E32 / E22 LoRa UART modules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransmissionMode {
+    /// Everything written by the host is sent to modules with the same address and channel.
+    Transparent,
+    /// First three bytes written by the host are target address (high byte first)
+    /// and channel, the rest is the payload.
+    Fixed,
+}
+
+/// Modem with per-device address and channel, which filters packets on reception,
+/// so only matching receivers output the payload to the host.
+/// Every `write` call stands for a single packet, as UART modules end the packet
+/// when the line goes idle. Long packets are split into parts of 255 bytes.
+///
+/// In the air, packet is framed as `address (2), channel, length, payload, CRC-16 (2)`,
+/// so a packet corrupted by collision is dropped.
+/// ```
+/// use proto_lab::{AddressedModem, NetworkSimulator, TransmissionMode, WirelessModemFake};
+///
+/// let simulator = NetworkSimulator::new(1);
+/// simulator.create_ether("air");
+/// let mut ether = simulator.get_ether("air").unwrap();
+///
+/// let mut modems: Vec<AddressedModem> = [(1, 5), (2, 5), (2, 6)]
+///     .iter()
+///     .map(|&(address, channel)| {
+///         let modem = WirelessModemFake::new(&format!("{}-{}", address, channel));
+///         ether.register_driver(modem.clone());
+///         AddressedModem::new(modem, address, channel)
+///     })
+///     .collect();
+///
+/// modems[0].set_mode(TransmissionMode::Fixed);
+/// modems[0].write(&[0x00, 0x02, 5, b'h', b'i']);
+/// simulator.run_ticks(10);
+///
+/// let mut buf = [0u8; 16];
+/// assert_eq!(modems[1].read(&mut buf), 2);
+/// assert_eq!(&buf[..2], b"hi");
+/// assert_eq!(modems[2].read(&mut buf), 0);
+/// ```
+pub struct AddressedModem {
+    modem: WirelessModemFake,
+    address: u16,
+    channel: u8,
+    mode: TransmissionMode,
+    from_air_buffer: Vec<u8>,
+    /// Head of `from_air_buffer` is known to be the start of a frame, unless the last frame
+    /// failed its CRC.
+    is_synced: bool,
+    payload_buffer: VecDeque<u8>,
+}
+
+impl AddressedModem {
+    /// Wraps the modem. Transmission mode is transparent by default.
+    pub fn new(modem: WirelessModemFake, address: u16, channel: u8) -> Self {
+        AddressedModem {
+            modem,
+            address,
+            channel,
+            mode: TransmissionMode::Transparent,
+            from_air_buffer: Vec::new(),
+            is_synced: true,
+            payload_buffer: VecDeque::new(),
+        }
+    }
+
+    pub fn set_mode(&mut self, mode: TransmissionMode) {
+        self.mode = mode;
+    }
+
+    pub fn get_mode(&self) -> TransmissionMode {
+        self.mode
+    }
+
+    pub fn set_address(&mut self, address: u16) {
+        self.address = address;
+    }
+
+    pub fn get_address(&self) -> u16 {
+        self.address
+    }
+
+    pub fn set_channel(&mut self, channel: u8) {
+        self.channel = channel;
+    }
+
+    pub fn get_channel(&self) -> u8 {
+        self.channel
+    }
+
+    /// Gets the wrapped modem.
+    pub fn get_modem(&self) -> &WirelessModemFake {
+        &self.modem
+    }
+
+    /// Sends the packet. In fixed mode, the packet shorter than three bytes
+    /// has no target and is dropped.
+    pub fn write(&mut self, buf: &[u8]) -> usize {
+        let (address, channel, payload) = match self.mode {
+            TransmissionMode::Transparent => (self.address, self.channel, buf),
+            TransmissionMode::Fixed => match buf {
+                [address_high, address_low, channel, payload @ ..] => (
+                    u16::from_be_bytes([*address_high, *address_low]),
+                    *channel,
+                    payload,
+                ),
+                _ => return buf.len(),
+            },
+        };
+
+        for part in payload.chunks(u8::MAX as usize) {
+            for byte in encode_air_frame(address, channel, part) {
+                self.modem.put_to_rx_pin(byte);
+            }
+        }
+
+        buf.len()
+    }
+
+    /// Reads payloads of the packets addressed to this modem.
+    pub fn read(&mut self, buf: &mut [u8]) -> usize {
+        self.receive_from_air();
+
+        let mut count_red: usize = 0;
+        for buf_vancant_place in buf.iter_mut() {
+            match self.payload_buffer.pop_front() {
+                Some(byte) => {
+                    *buf_vancant_place = byte;
+                    count_red += 1;
+                }
+                None => break,
+            }
+        }
+        count_red
+    }
+
+    /// Tells if there are payload bytes to be read.
+    pub fn readable(&mut self) -> bool {
+        self.receive_from_air();
+        !self.payload_buffer.is_empty()
+    }
+
+    fn receive_from_air(&mut self) {
+        while let Some(byte) = self.modem.get_from_tx_pin() {
+            self.from_air_buffer.push(byte);
+        }
+
+        // Frame at the head of the buffer is waited for until it is complete.
+        // Only a frame failing its CRC makes the parser look for the next frame start,
+        // so bytes corrupted by collisions are skipped. While looking for it, a false length
+        // at the head does not hold back a complete valid frame, which follows.
+        while let Some(&length) = self.from_air_buffer.get(3) {
+            let frame_length = AIR_HEADER_LENGTH + length as usize + AIR_CRC_LENGTH;
+            let Some(frame) = self.from_air_buffer.get(..frame_length) else {
+                match self.is_synced {
+                    true => break,
+                    false => match self.find_frame() {
+                        Some(start) => {
+                            self.from_air_buffer.drain(..start);
+                            continue;
+                        }
+                        None => break,
+                    },
+                }
+            };
+
+            let (content, crc) = frame.split_at(frame_length - AIR_CRC_LENGTH);
+            if air_crc(content) != crc {
+                self.from_air_buffer.remove(0);
+                self.is_synced = false;
+                continue;
+            }
+            self.is_synced = true;
+
+            let address = u16::from_be_bytes([frame[0], frame[1]]);
+            let is_addressed = address == self.address
+                || address == BROADCAST_ADDRESS
+                || self.address == BROADCAST_ADDRESS;
+
+            if frame[2] == self.channel && is_addressed {
+                self.payload_buffer.extend(&content[AIR_HEADER_LENGTH..]);
+            }
+            self.from_air_buffer.drain(..frame_length);
+        }
+    }
+
+    /// Finds start of the first complete frame with valid CRC past the head of the buffer.
+    fn find_frame(&self) -> Option<usize> {
+        (1..self.from_air_buffer.len()).find(|&start| {
+            let candidate = &self.from_air_buffer[start..];
+            let Some(&length) = candidate.get(3) else {
+                return false;
+            };
+            let frame_length = AIR_HEADER_LENGTH + length as usize + AIR_CRC_LENGTH;
+            candidate.get(..frame_length).is_some_and(|frame| {
+                let (content, crc) = frame.split_at(frame_length - AIR_CRC_LENGTH);
+                air_crc(content) == crc
+            })
+        })
+    }
+}
+
+/// Frames the part of the packet for the air.
+fn encode_air_frame(address: u16, channel: u8, part: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(AIR_HEADER_LENGTH + part.len() + AIR_CRC_LENGTH);
+    frame.extend_from_slice(&address.to_be_bytes());
+    frame.push(channel);
+    frame.push(part.len() as u8);
+    frame.extend_from_slice(part);
+    frame.extend(air_crc(&frame));
+    frame
+}
+
+impl embedded_io::ErrorType for AddressedModem {
+    type Error = core::convert::Infallible;
+}
+
+impl embedded_io::ReadReady for AddressedModem {
+    fn read_ready(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.readable())
+    }
+}
+
+impl embedded_io::Read for AddressedModem {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        Ok(AddressedModem::read(self, buf))
+    }
+}
+
+impl embedded_io::Write for AddressedModem {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        Ok(AddressedModem::write(self, buf))
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod addressed_modem_tests {
+    use super::*;
+    use crate::EtherSimulator;
+
+    fn transfer(ether: &EtherSimulator, ticks: usize) {
+        for _ in 0..ticks {
+            ether.start_tick();
+            ether.simulate();
+            ether.end_tick();
+        }
+    }
+
+    #[test]
+    fn test_air_crc_check_value() {
+        assert_eq!(air_crc(b"123456789"), 0x906Eu16.to_le_bytes());
+    }
+
+    #[test]
+    fn test_transparent_mode_reaches_same_address_and_channel() {
+        let mut ether = EtherSimulator::new("air");
+        let mut modems: Vec<AddressedModem> = [(1, 1), (1, 1), (2, 1), (1, 2)]
+            .iter()
+            .enumerate()
+            .map(|(i, &(address, channel))| {
+                let modem = WirelessModemFake::new(&i.to_string());
+                ether.register_driver(modem.clone());
+                AddressedModem::new(modem, address, channel)
+            })
+            .collect();
+
+        modems[0].write(b"abc");
+        transfer(&ether, 10);
+
+        let mut buf = [0u8; 8];
+        assert_eq!(modems[1].read(&mut buf), 3);
+        assert_eq!(modems[2].read(&mut buf), 0);
+        assert_eq!(modems[3].read(&mut buf), 0);
+    }
+
+    #[test]
+    fn test_broadcast_and_monitoring_addresses() {
+        let mut ether = EtherSimulator::new("air");
+        let mut modems: Vec<AddressedModem> = [1, 2, BROADCAST_ADDRESS]
+            .iter()
+            .map(|&address| {
+                let modem = WirelessModemFake::new(&address.to_string());
+                ether.register_driver(modem.clone());
+                AddressedModem::new(modem, address, 0)
+            })
+            .collect();
+
+        modems[0].set_mode(TransmissionMode::Fixed);
+        modems[0].write(&[0xFF, 0xFF, 0, b'x']);
+        transfer(&ether, 10);
+
+        let mut buf = [0u8; 8];
+        assert_eq!(modems[1].read(&mut buf), 1);
+        assert_eq!(modems[2].read(&mut buf), 1);
+
+        modems[0].write(&[0x00, 0x02, 0, b'y']);
+        transfer(&ether, 10);
+        assert_eq!(modems[1].read(&mut buf), 1);
+        assert_eq!(modems[2].read(&mut buf), 1);
+    }
+
+    #[test]
+    fn test_corrupted_frame_is_dropped() {
+        let modem = WirelessModemFake::new("");
+        let mut addressed_modem = AddressedModem::new(modem.clone(), 1, 1);
+
+        let mut corrupted = encode_air_frame(1, 1, b"a");
+        corrupted[4] ^= 0x01;
+        for byte in corrupted.into_iter().chain(encode_air_frame(1, 1, b"b")) {
+            modem.start_tick();
+            modem.put_to_device_network_side(byte);
+            modem.end_tick();
+        }
+
+        let mut buf = [0u8; 8];
+        assert_eq!(addressed_modem.read(&mut buf), 1);
+        assert_eq!(buf[0], b'b');
+    }
+
+    #[test]
+    fn test_long_frame_survives_polling_while_arriving() {
+        let mut ether = EtherSimulator::new("air");
+        let mut modems: Vec<AddressedModem> = (0..2)
+            .map(|i| {
+                let modem = WirelessModemFake::new(&i.to_string());
+                ether.register_driver(modem.clone());
+                AddressedModem::new(modem, 1, 1)
+            })
+            .collect();
+
+        // Payload carries a valid frame of its own, which shall not be taken for the real one.
+        let mut payload = vec![0x55; 100];
+        payload.extend(encode_air_frame(1, 1, b"fake"));
+        payload.resize(200, 0xAA);
+        modems[0].write(&payload);
+
+        let mut received = Vec::new();
+        let mut buf = [0u8; 256];
+        for _ in 0..300 {
+            transfer(&ether, 1);
+            let count = modems[1].read(&mut buf);
+            received.extend_from_slice(&buf[..count]);
+        }
+        assert_eq!(received, payload);
+    }
+}
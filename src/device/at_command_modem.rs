@@ -0,0 +1,450 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use super::{IODriverSimulator, WirelessModemFake};
+use crate::{uart::garble, EtherSimulator};
+
+const ESCAPE_SEQUENCE: &[u8] = b"+++";
+
+/// Settings of the modem, which can be changed by AT commands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AtSettings {
+    /// Index of the ether from `AtCommandModem::set_channel_ethers`.
+    pub channel: u8,
+    /// Transmission power. Simulator has no propagation model,
+    /// so only zero power, which mutes the transmitter, is observable.
+    pub power: u8,
+    /// Address of the module, which can be queried by the firmware.
+    pub address: u16,
+    /// UART baud rate of the module. Bytes are garbled if it differs from the baud rate of the host.
+    pub baud: u32,
+}
+
+impl Default for AtSettings {
+    fn default() -> Self {
+        AtSettings {
+            channel: 0,
+            power: 20,
+            address: 0,
+            baud: 9600,
+        }
+    }
+}
+
+/// Form of the AT command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AtRequest<'a> {
+    /// `AT+NAME=value`
+    Set(&'a str),
+    /// `AT+NAME?`
+    Query,
+    /// `AT+NAME`
+    Execute,
+}
+
+/// Outcome of the AT command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AtResponse {
+    /// Replies `OK`.
+    Ok,
+    /// Replies `+NAME:value` followed by `OK`.
+    Value(String),
+    /// Replies `ERROR`.
+    Error,
+}
+
+type AtHandler = Box<dyn FnMut(&mut AtSettings, AtRequest) -> AtResponse>;
+
+fn numeric_setting<T>(
+    request: AtRequest,
+    value: &mut T,
+    is_valid: impl Fn(&T) -> bool,
+) -> AtResponse
+where
+    T: std::str::FromStr + ToString,
+{
+    match request {
+        AtRequest::Query => AtResponse::Value(value.to_string()),
+        AtRequest::Set(text) => match text.trim().parse::<T>() {
+            Ok(new_value) if is_valid(&new_value) => {
+                *value = new_value;
+                AtResponse::Ok
+            }
+            _ => AtResponse::Error,
+        },
+        AtRequest::Execute => AtResponse::Error,
+    }
+}
+
+/// Modem, which is configured by AT commands over the same UART it transfers data with.
+/// It is in data mode by default, where it works as transparent `WirelessModemFake`.
+/// Command mode is entered by writing `+++` alone, or with `set_command_mode`, like with a mode pin.
+/// In command mode, the modem parses lines of AT commands and replies `OK` / `ERROR`.
+/// `ATO` returns back to data mode.
+///
+/// Default command set is `AT+CHANNEL`, `AT+POWER`, `AT+ADDR` and `AT+BAUD`.
+/// Each supports `=value` to set and `?` to query.
+/// ```
+/// use proto_lab::{AtCommandModem, WirelessModemFake};
+///
+/// let mut modem = AtCommandModem::new(WirelessModemFake::new("my_modem"));
+/// modem.write(b"+++");
+/// modem.write(b"AT+POWER=5\r\nAT+POWER?\r\nAT+NOPE\r\nATO\r\n");
+///
+/// let mut buf = [0u8; 64];
+/// let count = modem.read(&mut buf);
+/// assert_eq!(
+///     &buf[..count],
+///     b"OK\r\nOK\r\n+POWER:5\r\nOK\r\nERROR\r\nOK\r\n"
+/// );
+/// assert!(!modem.is_command_mode());
+/// assert_eq!(modem.get_settings().power, 5);
+/// ```
+pub struct AtCommandModem {
+    modem: WirelessModemFake,
+    settings: AtSettings,
+    host_baud: u32,
+    channel_ethers: Vec<EtherSimulator>,
+    commands: BTreeMap<String, AtHandler>,
+    is_command_mode: bool,
+    command_line: Vec<u8>,
+    responses: VecDeque<u8>,
+}
+
+impl AtCommandModem {
+    pub fn new(modem: WirelessModemFake) -> Self {
+        let mut at_command_modem = AtCommandModem {
+            modem,
+            settings: AtSettings::default(),
+            host_baud: AtSettings::default().baud,
+            channel_ethers: Vec::new(),
+            commands: BTreeMap::new(),
+            is_command_mode: false,
+            command_line: Vec::new(),
+            responses: VecDeque::new(),
+        };
+
+        at_command_modem.add_command("CHANNEL", |settings, request| {
+            numeric_setting(request, &mut settings.channel, |_| true)
+        });
+        at_command_modem.add_command("POWER", |settings, request| {
+            numeric_setting(request, &mut settings.power, |_| true)
+        });
+        at_command_modem.add_command("ADDR", |settings, request| {
+            numeric_setting(request, &mut settings.address, |_| true)
+        });
+        at_command_modem.add_command("BAUD", |settings, request| {
+            numeric_setting(request, &mut settings.baud, |&baud| baud > 0)
+        });
+
+        at_command_modem
+    }
+
+    /// Adds the command `AT+<name>` or replaces the existing one.
+    /// Settings changed by the handler are applied to the simulation.
+    pub fn add_command(
+        &mut self,
+        name: &str,
+        handler: impl FnMut(&mut AtSettings, AtRequest) -> AtResponse + 'static,
+    ) {
+        self.commands
+            .insert(name.to_ascii_uppercase(), Box::new(handler));
+    }
+
+    /// Removes the command `AT+<name>`, so it replies `ERROR`.
+    pub fn remove_command(&mut self, name: &str) {
+        self.commands.remove(&name.to_ascii_uppercase());
+    }
+
+    /// Sets ethers, which stand for the channels. Channel is the index of the ether.
+    /// The modem is moved into the ether of the current channel right away.
+    /// Setting the channel out of range replies `ERROR`.
+    /// ```
+    /// use proto_lab::{AtCommandModem, EtherSimulator, WirelessModemFake};
+    ///
+    /// let channels = vec![EtherSimulator::new("0"), EtherSimulator::new("1")];
+    /// let mut modem = AtCommandModem::new(WirelessModemFake::new("my_modem"));
+    /// modem.set_channel_ethers(channels.clone());
+    /// assert!(channels[0].get_driver("my_modem").is_some());
+    ///
+    /// modem.set_command_mode(true);
+    /// modem.write(b"AT+CHANNEL=1\r\nAT+CHANNEL=2\r\n");
+    ///
+    /// let mut buf = [0u8; 64];
+    /// let count = modem.read(&mut buf);
+    /// assert_eq!(&buf[..count], b"OK\r\nERROR\r\n");
+    /// assert!(channels[0].get_driver("my_modem").is_none());
+    /// assert!(channels[1].get_driver("my_modem").is_some());
+    /// ```
+    pub fn set_channel_ethers(&mut self, ethers: Vec<EtherSimulator>) {
+        for ether in self.channel_ethers.iter_mut() {
+            ether.unregister_driver(self.modem.get_name());
+        }
+        self.channel_ethers = ethers;
+        if let Some(ether) = self.channel_ethers.get_mut(self.settings.channel as usize) {
+            ether.register_driver(self.modem.clone());
+        }
+    }
+
+    /// Sets the baud rate the host UART runs with.
+    pub fn set_host_baud(&mut self, baud: u32) {
+        self.host_baud = baud;
+    }
+
+    /// Switches between command and data modes, like with a mode pin.
+    pub fn set_command_mode(&mut self, is_command_mode: bool) {
+        self.is_command_mode = is_command_mode;
+        self.command_line.clear();
+    }
+
+    pub fn is_command_mode(&self) -> bool {
+        self.is_command_mode
+    }
+
+    pub fn get_settings(&self) -> AtSettings {
+        self.settings
+    }
+
+    /// Gets the wrapped modem.
+    pub fn get_modem(&self) -> &WirelessModemFake {
+        &self.modem
+    }
+
+    /// Writes bytes from the host UART.
+    pub fn write(&mut self, buf: &[u8]) -> usize {
+        let bytes: Vec<u8> = buf
+            .iter()
+            .map(|&byte| self.received_from_host(byte))
+            .collect();
+
+        if !self.is_command_mode && bytes == ESCAPE_SEQUENCE {
+            self.set_command_mode(true);
+            self.respond(&AtResponse::Ok, "");
+            return buf.len();
+        }
+
+        for byte in bytes {
+            if self.is_command_mode {
+                self.put_to_command_line(byte);
+            } else if self.settings.power > 0 {
+                self.modem.put_to_rx_pin(byte);
+            }
+        }
+
+        buf.len()
+    }
+
+    /// Reads bytes into the host UART. Replies to commands are read first.
+    pub fn read(&mut self, buf: &mut [u8]) -> usize {
+        let mut count_red: usize = 0;
+        for buf_vancant_place in buf.iter_mut() {
+            let byte = match self.responses.pop_front() {
+                Some(byte) => Some(byte),
+                None if !self.is_command_mode => self
+                    .modem
+                    .get_from_tx_pin()
+                    .map(|byte| self.sent_to_host(byte)),
+                None => None,
+            };
+
+            match byte {
+                Some(byte) => {
+                    *buf_vancant_place = byte;
+                    count_red += 1;
+                }
+                None => break,
+            }
+        }
+        count_red
+    }
+
+    /// Tells if there are bytes to be read by the host.
+    pub fn readable(&self) -> bool {
+        !self.responses.is_empty() || (!self.is_command_mode && self.modem.readable())
+    }
+
+    fn received_from_host(&self, byte: u8) -> u8 {
+        match self.host_baud == self.settings.baud {
+            true => byte,
+            false => garble(byte, self.host_baud, self.settings.baud),
+        }
+    }
+
+    fn sent_to_host(&self, byte: u8) -> u8 {
+        match self.host_baud == self.settings.baud {
+            true => byte,
+            false => garble(byte, self.settings.baud, self.host_baud),
+        }
+    }
+
+    fn put_to_command_line(&mut self, byte: u8) {
+        match byte {
+            b'\r' | b'\n' => {
+                let line = String::from_utf8_lossy(&self.command_line).into_owned();
+                self.command_line.clear();
+                if !line.trim().is_empty() {
+                    self.execute(line.trim());
+                }
+            }
+            _ => self.command_line.push(byte),
+        }
+    }
+
+    fn execute(&mut self, line: &str) {
+        let upper_line = line.to_ascii_uppercase();
+
+        if upper_line == "AT" {
+            return self.respond(&AtResponse::Ok, "");
+        }
+        if upper_line == "ATO" {
+            self.respond(&AtResponse::Ok, "");
+            return self.set_command_mode(false);
+        }
+
+        let Some(command) = line.get(3..).filter(|_| upper_line.starts_with("AT+")) else {
+            return self.respond(&AtResponse::Error, "");
+        };
+
+        let (name, request) = match command.split_once('=') {
+            Some((name, value)) => (name, AtRequest::Set(value)),
+            None => match command.strip_suffix('?') {
+                Some(name) => (name, AtRequest::Query),
+                None => (command, AtRequest::Execute),
+            },
+        };
+        let name = name.to_ascii_uppercase();
+
+        let mut new_settings = self.settings;
+        let response = match self.commands.get_mut(&name) {
+            Some(handler) => handler(&mut new_settings, request),
+            None => AtResponse::Error,
+        };
+
+        let response = match response {
+            AtResponse::Error => AtResponse::Error,
+            _ if !self.can_apply_settings(&new_settings) => AtResponse::Error,
+            response => response,
+        };
+
+        // Reply goes out with old settings, like real modules do before switching baud rate.
+        self.respond(&response, &name);
+
+        if response != AtResponse::Error {
+            self.apply_settings(new_settings);
+        }
+    }
+
+    fn can_apply_settings(&self, new_settings: &AtSettings) -> bool {
+        self.channel_ethers.is_empty()
+            || (new_settings.channel as usize) < self.channel_ethers.len()
+    }
+
+    /// Applies new settings to the simulation.
+    fn apply_settings(&mut self, new_settings: AtSettings) {
+        if new_settings.channel != self.settings.channel && !self.channel_ethers.is_empty() {
+            self.channel_ethers[self.settings.channel as usize]
+                .unregister_driver(self.modem.get_name());
+            self.channel_ethers[new_settings.channel as usize].register_driver(self.modem.clone());
+        }
+
+        self.settings = new_settings;
+    }
+
+    fn respond(&mut self, response: &AtResponse, name: &str) {
+        let text = match response {
+            AtResponse::Ok => String::from("OK\r\n"),
+            AtResponse::Value(value) => format!("+{}:{}\r\nOK\r\n", name, value),
+            AtResponse::Error => String::from("ERROR\r\n"),
+        };
+        let bytes: Vec<u8> = text.bytes().map(|byte| self.sent_to_host(byte)).collect();
+        self.responses.extend(bytes);
+    }
+}
+
+impl embedded_io::ErrorType for AtCommandModem {
+    type Error = core::convert::Infallible;
+}
+
+impl embedded_io::ReadReady for AtCommandModem {
+    fn read_ready(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.readable())
+    }
+}
+
+impl embedded_io::Read for AtCommandModem {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        Ok(AtCommandModem::read(self, buf))
+    }
+}
+
+impl embedded_io::Write for AtCommandModem {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        Ok(AtCommandModem::write(self, buf))
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod at_command_modem_tests {
+    use super::*;
+
+    fn read_all(modem: &mut AtCommandModem) -> Vec<u8> {
+        let mut buf = [0u8; 256];
+        let count = modem.read(&mut buf);
+        buf[..count].to_vec()
+    }
+
+    #[test]
+    fn test_zero_power_mutes_transmitter() {
+        let mut modem = AtCommandModem::new(WirelessModemFake::new(""));
+        modem.set_command_mode(true);
+        modem.write(b"AT+POWER=0\r");
+        modem.set_command_mode(false);
+        assert_eq!(read_all(&mut modem), b"OK\r\n");
+
+        modem.write(b"a");
+        modem.get_modem().start_tick();
+        assert_eq!(modem.get_modem().get_from_device_network_side(), None);
+        modem.get_modem().end_tick();
+    }
+
+    #[test]
+    fn test_baud_mismatch_garbles_data() {
+        let mut modem = AtCommandModem::new(WirelessModemFake::new(""));
+        modem.write(b"+++");
+        modem.write(b"AT+BAUD=19200\r\n");
+        assert_eq!(read_all(&mut modem), b"OK\r\nOK\r\n");
+        modem.set_command_mode(false);
+
+        modem.write(b"a");
+        modem.get_modem().start_tick();
+        assert_ne!(modem.get_modem().get_from_device_network_side(), Some(b'a'));
+        modem.get_modem().end_tick();
+
+        modem.set_host_baud(19200);
+        modem.write(b"a");
+        modem.get_modem().start_tick();
+        assert_eq!(modem.get_modem().get_from_device_network_side(), Some(b'a'));
+        modem.get_modem().end_tick();
+    }
+
+    #[test]
+    fn test_custom_command() {
+        let mut modem = AtCommandModem::new(WirelessModemFake::new(""));
+        modem.add_command("RESET", |settings, request| match request {
+            AtRequest::Execute => {
+                *settings = AtSettings::default();
+                AtResponse::Ok
+            }
+            _ => AtResponse::Error,
+        });
+        modem.remove_command("ADDR");
+
+        modem.set_command_mode(true);
+        modem.write(b"AT+POWER=1\rAT+RESET\rAT+ADDR?\r");
+        assert_eq!(read_all(&mut modem), b"OK\r\nOK\r\nERROR\r\n");
+        assert_eq!(modem.get_settings(), AtSettings::default());
+    }
+}
@@ -0,0 +1,318 @@
+use std::sync::{Arc, Mutex};
+
+/// Highest identifier of the standard (11-bit) frame.
+pub const CAN_MAX_STANDARD_ID: u32 = 0x7FF;
+/// Highest identifier of the extended (29-bit) frame.
+pub const CAN_MAX_EXTENDED_ID: u32 = 0x1FFF_FFFF;
+/// Maximal data length of the classic CAN frame.
+pub const CAN_MAX_DATA_LENGTH: usize = 8;
+
+/// Data frame of the classic CAN.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CanFrame {
+    id: u32,
+    extended: bool,
+    data: Vec<u8>,
+}
+
+impl CanFrame {
+    /// Creates the frame with standard 11-bit identifier.
+    /// Panics if the identifier or the data length is out of range.
+    pub fn new(id: u32, data: &[u8]) -> Self {
+        if id > CAN_MAX_STANDARD_ID {
+            panic!("Standard CAN identifier {:#X} is out of range", id);
+        }
+        Self::new_checked(id, false, data)
+    }
+
+    /// Creates the frame with extended 29-bit identifier.
+    /// Panics if the identifier or the data length is out of range.
+    pub fn new_extended(id: u32, data: &[u8]) -> Self {
+        if id > CAN_MAX_EXTENDED_ID {
+            panic!("Extended CAN identifier {:#X} is out of range", id);
+        }
+        Self::new_checked(id, true, data)
+    }
+
+    fn new_checked(id: u32, extended: bool, data: &[u8]) -> Self {
+        if data.len() > CAN_MAX_DATA_LENGTH {
+            panic!("CAN frame can not carry {} bytes", data.len());
+        }
+        CanFrame {
+            id,
+            extended,
+            data: data.to_vec(),
+        }
+    }
+
+    pub fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn is_extended(&self) -> bool {
+        self.extended
+    }
+
+    pub fn get_data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Key, which orders the frames the same way, as bitwise arbitration does.
+    /// Dominant bits win, so the lower key wins. Standard frame wins the extended one
+    /// with the same base identifier, because of the recessive IDE bit of the extended one.
+    pub(crate) fn get_arbitration_key(&self) -> (u32, bool, u32) {
+        match self.extended {
+            true => (self.id >> 18, true, self.id & 0x3FFFF),
+            false => (self.id, false, 0),
+        }
+    }
+}
+
+/// Fault confinement state of the CAN controller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CanErrorState {
+    /// Both error counters are not above 127.
+    ErrorActive,
+    /// Any of the error counters is above 127.
+    ErrorPassive,
+    /// Transmit error counter went above 255. Controller neither transmits nor receives,
+    /// until it is `reset`.
+    BusOff,
+}
+
+struct InternalState {
+    tx_queue: Vec<CanFrame>,
+    rx_queue: Vec<CanFrame>,
+    acceptance_filters: Vec<(u32, u32)>,
+    transmit_error_counter: u32,
+    receive_error_counter: u32,
+    bus_off: bool,
+    arbitration_lost_count: u64,
+}
+
+/// CAN controller, which is connected to the `CanBus`.
+/// Queued frames are retransmitted automatically, until they win the arbitration
+/// and get transmitted without errors.
+pub struct CanController {
+    arc_mutexed_internal_state: Arc<Mutex<InternalState>>,
+    name: String,
+}
+
+impl CanController {
+    pub fn new(name: &str) -> Self {
+        CanController {
+            arc_mutexed_internal_state: Arc::new(Mutex::new(InternalState {
+                tx_queue: Vec::new(),
+                rx_queue: Vec::new(),
+                acceptance_filters: Vec::new(),
+                transmit_error_counter: 0,
+                receive_error_counter: 0,
+                bus_off: false,
+                arbitration_lost_count: 0,
+            })),
+            name: String::from(name),
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    fn lock_internal_state(&self) -> std::sync::MutexGuard<'_, InternalState> {
+        self.arc_mutexed_internal_state
+            .lock()
+            .unwrap_or_else(|_| panic!("Fail to lock mutex for controller :{}", self.name))
+    }
+
+    /// Queues the frame for transmission.
+    pub fn transmit(&self, frame: CanFrame) {
+        self.lock_internal_state().tx_queue.push(frame);
+    }
+
+    /// Takes the oldest received frame, which passed the acceptance filters.
+    pub fn receive(&self) -> Option<CanFrame> {
+        let mut locked_internal_state = self.lock_internal_state();
+        match locked_internal_state.rx_queue.is_empty() {
+            true => None,
+            false => Some(locked_internal_state.rx_queue.remove(0)),
+        }
+    }
+
+    /// Gets count of frames, which are not transmitted yet.
+    pub fn get_pending_count(&self) -> usize {
+        self.lock_internal_state().tx_queue.len()
+    }
+
+    /// Accepts frames, which identifier matches `id` in all the bits set in `mask`.
+    /// Without any filter all the frames are accepted.
+    /// ```
+    /// use proto_lab::{CanBus, CanController, CanFrame, MediumSimulator};
+    ///
+    /// let mut bus = CanBus::new("bus");
+    /// let sender = CanController::new("sender");
+    /// let receiver = CanController::new("receiver");
+    /// bus.register_controller(sender.clone());
+    /// bus.register_controller(receiver.clone());
+    ///
+    /// receiver.add_acceptance_filter(0x100, 0x700);
+    /// sender.transmit(CanFrame::new(0x123, &[1]));
+    /// sender.transmit(CanFrame::new(0x223, &[2]));
+    ///
+    /// for _ in 0..2 {
+    ///     bus.start_tick();
+    ///     bus.simulate();
+    ///     bus.end_tick();
+    /// }
+    ///
+    /// assert_eq!(receiver.receive(), Some(CanFrame::new(0x123, &[1])));
+    /// assert_eq!(receiver.receive(), None);
+    /// ```
+    pub fn add_acceptance_filter(&self, id: u32, mask: u32) {
+        self.lock_internal_state()
+            .acceptance_filters
+            .push((id, mask));
+    }
+
+    pub fn clear_acceptance_filters(&self) {
+        self.lock_internal_state().acceptance_filters.clear();
+    }
+
+    /// Gets transmit error counter.
+    pub fn get_transmit_error_counter(&self) -> u32 {
+        self.lock_internal_state().transmit_error_counter
+    }
+
+    /// Gets receive error counter.
+    pub fn get_receive_error_counter(&self) -> u32 {
+        self.lock_internal_state().receive_error_counter
+    }
+
+    pub fn get_error_state(&self) -> CanErrorState {
+        let locked_internal_state = self.lock_internal_state();
+        if locked_internal_state.bus_off {
+            CanErrorState::BusOff
+        } else if locked_internal_state.transmit_error_counter > 127
+            || locked_internal_state.receive_error_counter > 127
+        {
+            CanErrorState::ErrorPassive
+        } else {
+            CanErrorState::ErrorActive
+        }
+    }
+
+    /// Gets count of times the controller lost the arbitration.
+    pub fn get_arbitration_lost_count(&self) -> u64 {
+        self.lock_internal_state().arbitration_lost_count
+    }
+
+    /// Resets the controller, which recovers it from bus off state.
+    /// Error counters are cleared, queued frames are kept.
+    pub fn reset(&self) {
+        let mut locked_internal_state = self.lock_internal_state();
+        locked_internal_state.transmit_error_counter = 0;
+        locked_internal_state.receive_error_counter = 0;
+        locked_internal_state.bus_off = false;
+    }
+
+    pub(crate) fn is_bus_off(&self) -> bool {
+        self.lock_internal_state().bus_off
+    }
+
+    /// Gets the queued frame with the highest priority.
+    pub(crate) fn get_pending_frame(&self) -> Option<CanFrame> {
+        let locked_internal_state = self.lock_internal_state();
+        if locked_internal_state.bus_off {
+            return None;
+        }
+        locked_internal_state
+            .tx_queue
+            .iter()
+            .min_by_key(|frame| frame.get_arbitration_key())
+            .cloned()
+    }
+
+    pub(crate) fn on_transmit_success(&self, frame: &CanFrame) {
+        let mut locked_internal_state = self.lock_internal_state();
+        if let Some(position) = locked_internal_state
+            .tx_queue
+            .iter()
+            .position(|queued| queued == frame)
+        {
+            locked_internal_state.tx_queue.remove(position);
+        }
+        locked_internal_state.transmit_error_counter = locked_internal_state
+            .transmit_error_counter
+            .saturating_sub(1);
+    }
+
+    pub(crate) fn on_arbitration_lost(&self) {
+        self.lock_internal_state().arbitration_lost_count += 1;
+    }
+
+    /// Frame is kept queued for retransmission.
+    pub(crate) fn on_transmit_error(&self) {
+        let mut locked_internal_state = self.lock_internal_state();
+        locked_internal_state.transmit_error_counter += 8;
+        if locked_internal_state.transmit_error_counter > 255 {
+            locked_internal_state.bus_off = true;
+        }
+    }
+
+    /// Nobody acknowledged the frame. Error passive transmitter does not count it,
+    /// so the lonely node does not go bus off.
+    pub(crate) fn on_acknowledgement_error(&self) {
+        let error_passive = self.get_error_state() == CanErrorState::ErrorPassive;
+        if !error_passive {
+            self.on_transmit_error();
+        }
+    }
+
+    pub(crate) fn on_receive_success(&self, frame: &CanFrame) {
+        let mut locked_internal_state = self.lock_internal_state();
+        locked_internal_state.receive_error_counter = locked_internal_state
+            .receive_error_counter
+            .saturating_sub(1);
+
+        let accepted = locked_internal_state.acceptance_filters.is_empty()
+            || locked_internal_state
+                .acceptance_filters
+                .iter()
+                .any(|(id, mask)| frame.get_id() & mask == id & mask);
+
+        if accepted {
+            locked_internal_state.rx_queue.push(frame.clone());
+        }
+    }
+
+    pub(crate) fn on_receive_error(&self) {
+        self.lock_internal_state().receive_error_counter += 1;
+    }
+}
+
+impl Clone for CanController {
+    fn clone(&self) -> Self {
+        CanController {
+            arc_mutexed_internal_state: Arc::clone(&self.arc_mutexed_internal_state),
+            name: self.name.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod can_controller_tests {
+    use super::*;
+
+    #[test]
+    fn test_standard_frame_wins_extended_with_same_base_id() {
+        let standard = CanFrame::new(0x123, &[]);
+        let extended = CanFrame::new_extended(0x123 << 18, &[]);
+        assert!(standard.get_arbitration_key() < extended.get_arbitration_key());
+        assert!(CanFrame::new(0x122, &[]).get_arbitration_key() < extended.get_arbitration_key());
+    }
+
+    #[test]
+    #[should_panic(expected = "CAN frame can not carry 9 bytes")]
+    fn test_too_long_frame_panics() {
+        CanFrame::new(1, &[0; 9]);
+    }
+}
@@ -0,0 +1,92 @@
+/// Current draw of the modem in every state, used to estimate consumed energy.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EnergyModel {
+    pub voltage_v: f64,
+    pub tx_current_ma: f64,
+    pub rx_current_ma: f64,
+    pub idle_current_ma: f64,
+    /// Current while the radio sleeps.
+    pub sleep_current_ma: f64,
+    /// Capacity of the battery. The modem is powered off once it is depleted.
+    /// `None` stands for unlimited power supply.
+    pub battery_capacity_mah: Option<f64>,
+}
+
+/// Energy consumed by the modem so far.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct EnergyReport {
+    pub tx_ticks: u64,
+    pub rx_ticks: u64,
+    pub idle_ticks: u64,
+    pub sleep_ticks: u64,
+    pub consumed_mah: f64,
+    pub consumed_mj: f64,
+    /// Charge left in the battery, `None` for unlimited power supply.
+    pub remaining_mah: Option<f64>,
+}
+
+impl EnergyReport {
+    /// Tells if the battery is depleted.
+    pub fn is_depleted(&self) -> bool {
+        matches!(self.remaining_mah, Some(remaining_mah) if remaining_mah <= 0.0)
+    }
+}
+
+/// State of the modem for the energy accounting.
+pub(crate) enum EnergyState {
+    Transmit,
+    Receive,
+    Idle,
+    Sleep,
+}
+
+pub(crate) struct EnergyMeter {
+    model: EnergyModel,
+    report: EnergyReport,
+}
+
+impl EnergyMeter {
+    pub(crate) fn new(model: EnergyModel) -> Self {
+        EnergyMeter {
+            model,
+            report: EnergyReport {
+                remaining_mah: model.battery_capacity_mah,
+                ..EnergyReport::default()
+            },
+        }
+    }
+
+    pub(crate) fn get_report(&self) -> EnergyReport {
+        self.report
+    }
+
+    /// Accounts one tick of given duration spent in the state.
+    pub(crate) fn consume_tick(&mut self, state: EnergyState, ms_per_tick: u64) {
+        let current_ma = match state {
+            EnergyState::Transmit => {
+                self.report.tx_ticks += 1;
+                self.model.tx_current_ma
+            }
+            EnergyState::Receive => {
+                self.report.rx_ticks += 1;
+                self.model.rx_current_ma
+            }
+            EnergyState::Idle => {
+                self.report.idle_ticks += 1;
+                self.model.idle_current_ma
+            }
+            EnergyState::Sleep => {
+                self.report.sleep_ticks += 1;
+                self.model.sleep_current_ma
+            }
+        };
+
+        let consumed_mah = current_ma * ms_per_tick as f64 / 3_600_000.0;
+        self.report.consumed_mah += consumed_mah;
+        self.report.consumed_mj += current_ma * self.model.voltage_v * ms_per_tick as f64 / 1000.0;
+
+        if let Some(ref mut remaining_mah) = self.report.remaining_mah {
+            *remaining_mah = (*remaining_mah - consumed_mah).max(0.0);
+        }
+    }
+}
@@ -0,0 +1,244 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+/// Maximal payload length of the Ethernet frame.
+pub const ETHERNET_MTU: usize = 1500;
+/// Length of the destination, source and ethertype fields.
+pub const ETHERNET_HEADER_LENGTH: usize = 14;
+
+/// Hardware address of the Ethernet device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MacAddress(pub [u8; 6]);
+
+impl MacAddress {
+    pub const BROADCAST: MacAddress = MacAddress([0xFF; 6]);
+
+    pub fn is_broadcast(&self) -> bool {
+        *self == Self::BROADCAST
+    }
+
+    /// Tells if the group bit is set. Broadcast is multicast as well.
+    pub fn is_multicast(&self) -> bool {
+        self.0[0] & 0x01 != 0
+    }
+}
+
+impl core::fmt::Display for MacAddress {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let [a, b, c, d, e, g] = self.0;
+        write!(f, "{a:02x}:{b:02x}:{c:02x}:{d:02x}:{e:02x}:{g:02x}")
+    }
+}
+
+/// Ethernet II frame without preamble and frame check sequence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EthernetFrame {
+    destination: MacAddress,
+    source: MacAddress,
+    ethertype: u16,
+    payload: Vec<u8>,
+}
+
+impl EthernetFrame {
+    /// Panics if payload is longer than `ETHERNET_MTU`.
+    pub fn new(
+        destination: MacAddress,
+        source: MacAddress,
+        ethertype: u16,
+        payload: &[u8],
+    ) -> Self {
+        if payload.len() > ETHERNET_MTU {
+            panic!("Ethernet frame can not carry {} bytes", payload.len());
+        }
+        EthernetFrame {
+            destination,
+            source,
+            ethertype,
+            payload: payload.to_vec(),
+        }
+    }
+
+    pub fn get_destination(&self) -> MacAddress {
+        self.destination
+    }
+
+    pub fn get_source(&self) -> MacAddress {
+        self.source
+    }
+
+    pub fn get_ethertype(&self) -> u16 {
+        self.ethertype
+    }
+
+    pub fn get_payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    /// Gets count of bytes the frame takes on the wire.
+    pub fn get_length(&self) -> usize {
+        ETHERNET_HEADER_LENGTH + self.payload.len()
+    }
+
+    /// Serializes the frame into the bytes, as it is sent over the wire.
+    /// ```
+    /// use proto_lab::{EthernetFrame, MacAddress};
+    ///
+    /// let frame = EthernetFrame::new(MacAddress::BROADCAST, MacAddress([2, 0, 0, 0, 0, 1]), 0x0800, b"hi");
+    /// let bytes = frame.to_bytes();
+    /// assert_eq!(bytes.len(), 16);
+    /// assert_eq!(EthernetFrame::from_bytes(&bytes), Some(frame));
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.get_length());
+        bytes.extend_from_slice(&self.destination.0);
+        bytes.extend_from_slice(&self.source.0);
+        bytes.extend_from_slice(&self.ethertype.to_be_bytes());
+        bytes.extend_from_slice(&self.payload);
+        bytes
+    }
+
+    /// Parses the frame from the bytes. Gives `None` if the bytes are too short or too long.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < ETHERNET_HEADER_LENGTH
+            || bytes.len() > ETHERNET_HEADER_LENGTH + ETHERNET_MTU
+        {
+            return None;
+        }
+        let mac = |offset: usize| {
+            let mut address = [0u8; 6];
+            address.copy_from_slice(&bytes[offset..offset + 6]);
+            MacAddress(address)
+        };
+        Some(EthernetFrame {
+            destination: mac(0),
+            source: mac(6),
+            ethertype: u16::from_be_bytes([bytes[12], bytes[13]]),
+            payload: bytes[ETHERNET_HEADER_LENGTH..].to_vec(),
+        })
+    }
+}
+
+struct InternalState {
+    tx_queue: VecDeque<EthernetFrame>,
+    rx_queue: VecDeque<EthernetFrame>,
+    multicast_groups: Vec<MacAddress>,
+    promiscuous: bool,
+}
+
+/// Network interface card, which is connected to the port of `EthernetSwitch`.
+/// It receives frames addressed to its MAC address, broadcast and joined multicast groups.
+pub struct EthernetNic {
+    arc_mutexed_internal_state: Arc<Mutex<InternalState>>,
+    name: String,
+    mac_address: MacAddress,
+}
+
+impl EthernetNic {
+    pub fn new(name: &str, mac_address: MacAddress) -> Self {
+        EthernetNic {
+            arc_mutexed_internal_state: Arc::new(Mutex::new(InternalState {
+                tx_queue: VecDeque::new(),
+                rx_queue: VecDeque::new(),
+                multicast_groups: Vec::new(),
+                promiscuous: false,
+            })),
+            name: String::from(name),
+            mac_address,
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_mac_address(&self) -> MacAddress {
+        self.mac_address
+    }
+
+    fn lock_internal_state(&self) -> std::sync::MutexGuard<'_, InternalState> {
+        self.arc_mutexed_internal_state
+            .lock()
+            .unwrap_or_else(|_| panic!("Fail to lock mutex for nic :{}", self.name))
+    }
+
+    /// Queues the frame for sending.
+    pub fn send(&self, frame: EthernetFrame) {
+        self.lock_internal_state().tx_queue.push_back(frame);
+    }
+
+    /// Queues the frame from this NIC to the destination.
+    pub fn send_to(&self, destination: MacAddress, ethertype: u16, payload: &[u8]) {
+        self.send(EthernetFrame::new(
+            destination,
+            self.mac_address,
+            ethertype,
+            payload,
+        ));
+    }
+
+    /// Takes the oldest received frame.
+    pub fn receive(&self) -> Option<EthernetFrame> {
+        self.lock_internal_state().rx_queue.pop_front()
+    }
+
+    /// Starts receiving frames sent to the multicast group.
+    pub fn join_multicast_group(&self, group: MacAddress) {
+        if !group.is_multicast() {
+            panic!("{} is not multicast address", group);
+        }
+        self.lock_internal_state().multicast_groups.push(group);
+    }
+
+    pub fn leave_multicast_group(&self, group: MacAddress) {
+        self.lock_internal_state()
+            .multicast_groups
+            .retain(|joined| *joined != group);
+    }
+
+    /// Makes the NIC receive all the frames reaching it, whatever their destination is.
+    pub fn set_promiscuous(&self, promiscuous: bool) {
+        self.lock_internal_state().promiscuous = promiscuous;
+    }
+
+    /// Tells if all the queued frames are sent out.
+    pub fn is_transmission_complete(&self) -> bool {
+        self.lock_internal_state().tx_queue.is_empty()
+    }
+
+    pub(crate) fn peek_frame_length(&self) -> Option<usize> {
+        self.lock_internal_state()
+            .tx_queue
+            .front()
+            .map(|frame| frame.get_length())
+    }
+
+    pub(crate) fn take_frame(&self) -> Option<EthernetFrame> {
+        self.lock_internal_state().tx_queue.pop_front()
+    }
+
+    pub(crate) fn put_frame(&self, frame: EthernetFrame) {
+        let mut locked_internal_state = self.lock_internal_state();
+        let destination = frame.get_destination();
+        let accepted = locked_internal_state.promiscuous
+            || destination == self.mac_address
+            || destination.is_broadcast()
+            || locked_internal_state
+                .multicast_groups
+                .contains(&destination);
+        if accepted {
+            locked_internal_state.rx_queue.push_back(frame);
+        }
+    }
+}
+
+impl Clone for EthernetNic {
+    fn clone(&self) -> Self {
+        EthernetNic {
+            arc_mutexed_internal_state: Arc::clone(&self.arc_mutexed_internal_state),
+            name: self.name.clone(),
+            mac_address: self.mac_address,
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use std::collections::VecDeque;
+
+use super::{IODriverSimulator, WirelessModemFake};
+use crate::{FrameDecoder, Framing};
+
+/// Modem, which sends and receives whole frames over the byte stream of `WirelessModemFake`.
+/// Frames, which got corrupted on the air, are dropped if the framing can notice it.
+/// ```
+/// use proto_lab::{FramedModem, Framing, HdlcCrc, NetworkSimulator, WirelessModemFake};
+///
+/// let simulator = NetworkSimulator::new(1);
+/// simulator.create_ether("air");
+/// let mut ether = simulator.get_ether("air").unwrap();
+/// let mut sender = FramedModem::new(WirelessModemFake::new("sender"), Framing::Hdlc(HdlcCrc::Crc16));
+/// let mut receiver = FramedModem::new(WirelessModemFake::new("receiver"), Framing::Hdlc(HdlcCrc::Crc16));
+/// ether.register_driver(sender.get_modem().clone());
+/// ether.register_driver(receiver.get_modem().clone());
+///
+/// sender.send_frame(b"one");
+/// sender.send_frame(b"two");
+/// simulator.run_ticks(30);
+///
+/// assert_eq!(receiver.recv_frame(), Some(b"one".to_vec()));
+/// assert_eq!(receiver.recv_frame(), Some(b"two".to_vec()));
+/// assert_eq!(receiver.recv_frame(), None);
+/// ```
+pub struct FramedModem {
+    modem: WirelessModemFake,
+    decoder: FrameDecoder,
+    frames: VecDeque<Vec<u8>>,
+}
+
+impl FramedModem {
+    pub fn new(modem: WirelessModemFake, framing: Framing) -> Self {
+        FramedModem {
+            modem,
+            decoder: FrameDecoder::new(framing),
+            frames: VecDeque::new(),
+        }
+    }
+
+    /// Same as `new`, but drops received frames longer than `max_frame_length`.
+    pub fn with_max_frame_length(
+        modem: WirelessModemFake,
+        framing: Framing,
+        max_frame_length: usize,
+    ) -> Self {
+        FramedModem {
+            modem,
+            decoder: FrameDecoder::with_max_frame_length(framing, max_frame_length),
+            frames: VecDeque::new(),
+        }
+    }
+
+    /// Gets the wrapped modem.
+    pub fn get_modem(&self) -> &WirelessModemFake {
+        &self.modem
+    }
+
+    pub fn get_framing(&self) -> Framing {
+        self.decoder.get_framing()
+    }
+
+    /// Gets count of received frames, which were dropped as corrupted or too long.
+    pub fn get_dropped_frame_count(&self) -> u64 {
+        self.decoder.get_dropped_frame_count()
+    }
+
+    /// Encodes the payload and writes it into the modem.
+    pub fn send_frame(&mut self, payload: &[u8]) {
+        for byte in self.decoder.get_framing().encode(payload) {
+            self.modem.put_to_rx_pin(byte);
+        }
+    }
+
+    /// Takes the oldest received frame.
+    pub fn recv_frame(&mut self) -> Option<Vec<u8>> {
+        self.receive_from_air();
+        self.frames.pop_front()
+    }
+
+    /// Tells if there are received frames to be taken.
+    pub fn has_frame(&mut self) -> bool {
+        self.receive_from_air();
+        !self.frames.is_empty()
+    }
+
+    fn receive_from_air(&mut self) {
+        while let Some(byte) = self.modem.get_from_tx_pin() {
+            if let Some(frame) = self.decoder.push(byte) {
+                self.frames.push_back(frame);
+            }
+        }
+    }
+}
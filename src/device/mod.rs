@@ -1,9 +1,32 @@
+mod addressed_modem;
+mod at_command_modem;
+mod can_controller;
+mod energy;
+mod ethernet_nic;
+mod framed_modem;
+mod pins;
+mod rs485_transceiver;
+mod serial_port;
 mod traits;
 // mod wired_modem;
 mod wireless_modem;
 
 pub use {
+    addressed_modem::{AddressedModem, TransmissionMode, BROADCAST_ADDRESS},
+    at_command_modem::{AtCommandModem, AtRequest, AtResponse, AtSettings},
+    can_controller::{
+        CanController, CanErrorState, CanFrame, CAN_MAX_DATA_LENGTH, CAN_MAX_EXTENDED_ID,
+        CAN_MAX_STANDARD_ID,
+    },
+    energy::{EnergyModel, EnergyReport},
+    ethernet_nic::{EthernetFrame, EthernetNic, MacAddress, ETHERNET_HEADER_LENGTH, ETHERNET_MTU},
+    framed_modem::FramedModem,
+    pins::{ModePinFake, StatusPin, StatusPinFake},
+    rs485_transceiver::Rs485TransceiverFake,
+    serial_port::{SerialError, SerialPortFake},
     traits::IODriverSimulator,
-    wireless_modem::WirelessModemFake,
+    wireless_modem::{RadioMode, WirelessModemFake},
     /*wired_modem::WiredModemFake*/
 };
+
+pub(crate) use serial_port::LineSymbol;
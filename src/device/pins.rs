@@ -0,0 +1,144 @@
+use super::{IODriverSimulator, RadioMode, WirelessModemFake};
+
+/// Digital outputs of the modem, which tell the firmware about modem state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusPin {
+    /// High while the modem is busy, see `WirelessModemFake::is_busy`.
+    Busy,
+    /// High while there are bytes to be read from TX pin. Can be used as IRQ line.
+    DataAvailable,
+    /// High while the radio is receiving, see `WirelessModemFake::is_receiving`.
+    RxInProgress,
+}
+
+/// Status output of the modem. It is an input from the firmware point of view.
+/// With the `embedded-hal` feature it implements `embedded_hal::digital::InputPin`.
+/// ```
+/// use proto_lab::{IODriverSimulator, StatusPin, WirelessModemFake};
+///
+/// let device = WirelessModemFake::new("my_modem");
+/// let data_available = device.get_status_pin(StatusPin::DataAvailable);
+/// assert!(data_available.is_low());
+///
+/// device.start_tick();
+/// device.put_to_device_network_side(1);
+/// device.end_tick();
+/// assert!(data_available.is_high());
+/// ```
+#[derive(Clone)]
+pub struct StatusPinFake {
+    modem: WirelessModemFake,
+    pin: StatusPin,
+}
+
+impl StatusPinFake {
+    pub(crate) fn new(modem: WirelessModemFake, pin: StatusPin) -> Self {
+        StatusPinFake { modem, pin }
+    }
+
+    pub fn is_high(&self) -> bool {
+        match self.pin {
+            StatusPin::Busy => self.modem.is_busy(),
+            StatusPin::DataAvailable => self.modem.readable(),
+            StatusPin::RxInProgress => self.modem.is_receiving(),
+        }
+    }
+
+    pub fn is_low(&self) -> bool {
+        !self.is_high()
+    }
+}
+
+/// Mode select input of the modem. Driving it high puts the radio to sleep,
+/// driving it low wakes the radio up. It is an output from the firmware point of view.
+/// With the `embedded-hal` feature it implements `embedded_hal::digital::OutputPin`.
+/// ```
+/// use proto_lab::{RadioMode, WirelessModemFake};
+///
+/// let device = WirelessModemFake::new("my_modem");
+/// let mut mode_pin = device.get_mode_pin();
+///
+/// mode_pin.set_high();
+/// assert_eq!(device.get_mode(), RadioMode::Sleep);
+/// mode_pin.set_low();
+/// assert_eq!(device.get_mode(), RadioMode::Normal);
+/// ```
+#[derive(Clone)]
+pub struct ModePinFake {
+    modem: WirelessModemFake,
+}
+
+impl ModePinFake {
+    pub(crate) fn new(modem: WirelessModemFake) -> Self {
+        ModePinFake { modem }
+    }
+
+    pub fn set_high(&mut self) {
+        self.modem.set_mode(RadioMode::Sleep);
+    }
+
+    pub fn set_low(&mut self) {
+        self.modem.set_mode(RadioMode::Normal);
+    }
+}
+
+#[cfg(feature = "embedded-hal")]
+impl embedded_hal::digital::ErrorType for StatusPinFake {
+    type Error = core::convert::Infallible;
+}
+
+#[cfg(feature = "embedded-hal")]
+impl embedded_hal::digital::InputPin for StatusPinFake {
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(StatusPinFake::is_high(self))
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(StatusPinFake::is_low(self))
+    }
+}
+
+#[cfg(feature = "embedded-hal")]
+impl embedded_hal::digital::ErrorType for ModePinFake {
+    type Error = core::convert::Infallible;
+}
+
+#[cfg(feature = "embedded-hal")]
+impl embedded_hal::digital::OutputPin for ModePinFake {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        ModePinFake::set_low(self);
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        ModePinFake::set_high(self);
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "embedded-hal"))]
+mod pins_tests {
+    use embedded_hal::digital::{InputPin, OutputPin};
+
+    use super::*;
+
+    #[test]
+    fn test_pins_through_embedded_hal_traits() {
+        let device = WirelessModemFake::new("");
+        let mut data_available = device.get_status_pin(StatusPin::DataAvailable);
+        let mut mode_pin = device.get_mode_pin();
+        assert_eq!(InputPin::is_high(&mut data_available), Ok(false));
+        assert_eq!(InputPin::is_low(&mut data_available), Ok(true));
+
+        device.start_tick();
+        device.put_to_device_network_side(1);
+        device.end_tick();
+        assert_eq!(InputPin::is_high(&mut data_available), Ok(true));
+        assert_eq!(InputPin::is_low(&mut data_available), Ok(false));
+
+        assert_eq!(OutputPin::set_high(&mut mode_pin), Ok(()));
+        assert_eq!(device.get_mode(), RadioMode::Sleep);
+        assert_eq!(OutputPin::set_low(&mut mode_pin), Ok(()));
+        assert_eq!(device.get_mode(), RadioMode::Normal);
+    }
+}
@@ -0,0 +1,316 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use super::IODriverSimulator;
+
+//  Diagram of RS-485 transceiver, like MAX485.
+//
+//```
+//                (Bus side)
+//                  A    B
+//                  |    |
+//   +--------------|----|------------------+
+//   | Transceiver  +-+--+                  |
+//   |               / \                    |
+//   |     DE --->  /   \  <--- RE          |
+//   |        +->--+     +-->--+            |
+//   |        |                |            |
+//   |   to_bus buffer    from_bus buffer   |
+//   |        |                |            |
+//   |     RX pin (DI)     TX pin (RO)      |
+//   +--------------------------------------+
+//```
+//
+// Bytes written into RX pin are shifted out one per tick, and are driven
+// on the bus only while DE is asserted, otherwise they are lost.
+enum TickState {
+    InTick,
+    OffTick,
+}
+
+struct InternalState {
+    tick_state: TickState,
+    to_bus_buffer: VecDeque<u8>,
+    from_bus_buffer: VecDeque<u8>,
+    shifting_byte: Option<u8>,
+    driver_enabled: bool,
+    receiver_enabled: bool,
+    tick_line_byte: Option<u8>,
+    tick_contention: bool,
+    idle_ticks: u64,
+    contention_count: u64,
+}
+
+/// Half-duplex RS-485 transceiver with driver enable (DE) and receiver enable (RE) control.
+/// RE is active low on real chips, here `set_receiver_enable(true)` stands for RE pulled low.
+pub struct Rs485TransceiverFake {
+    arc_mutexed_internal_state: Arc<Mutex<InternalState>>,
+    name: String,
+}
+
+impl Rs485TransceiverFake {
+    /// Creates the transceiver with disabled driver and enabled receiver.
+    pub fn new(name: &str) -> Self {
+        Rs485TransceiverFake {
+            arc_mutexed_internal_state: Arc::new(Mutex::new(InternalState {
+                tick_state: TickState::OffTick,
+                to_bus_buffer: VecDeque::new(),
+                from_bus_buffer: VecDeque::new(),
+                shifting_byte: None,
+                driver_enabled: false,
+                receiver_enabled: true,
+                tick_line_byte: None,
+                tick_contention: false,
+                idle_ticks: 0,
+                contention_count: 0,
+            })),
+            name: String::from(name),
+        }
+    }
+
+    /// Drives DE pin. Bytes are driven on the bus only while it is asserted.
+    pub fn set_driver_enable(&self, enabled: bool) {
+        let mut locked_internal_state = self
+            .arc_mutexed_internal_state
+            .lock()
+            .unwrap_or_else(|_| panic!("Fail to lock mutex for transceiver :{}", self.name));
+
+        locked_internal_state.driver_enabled = enabled;
+    }
+
+    /// Drives RE pin. Bytes from the bus are received only while it is enabled.
+    pub fn set_receiver_enable(&self, enabled: bool) {
+        let mut locked_internal_state = self
+            .arc_mutexed_internal_state
+            .lock()
+            .unwrap_or_else(|_| panic!("Fail to lock mutex for transceiver :{}", self.name));
+
+        locked_internal_state.receiver_enabled = enabled;
+    }
+
+    /// Tells if all the written bytes are shifted out, so DE can be released.
+    pub fn is_transmission_complete(&self) -> bool {
+        let locked_internal_state = self
+            .arc_mutexed_internal_state
+            .lock()
+            .unwrap_or_else(|_| panic!("Fail to lock mutex for transceiver :{}", self.name));
+
+        locked_internal_state.to_bus_buffer.is_empty()
+            && locked_internal_state.shifting_byte.is_none()
+    }
+
+    /// Tells if nobody drove the bus during the last tick.
+    pub fn is_line_idle(&self) -> bool {
+        self.get_idle_ticks() > 0
+    }
+
+    /// Gets count of ticks since somebody drove the bus for the last time.
+    /// Can be used to detect end of the frame by the idle line.
+    pub fn get_idle_ticks(&self) -> u64 {
+        let locked_internal_state = self
+            .arc_mutexed_internal_state
+            .lock()
+            .unwrap_or_else(|_| panic!("Fail to lock mutex for transceiver :{}", self.name));
+
+        locked_internal_state.idle_ticks
+    }
+
+    /// Gets count of received bytes, which were corrupted by bus contention.
+    pub fn get_contention_count(&self) -> u64 {
+        let locked_internal_state = self
+            .arc_mutexed_internal_state
+            .lock()
+            .unwrap_or_else(|_| panic!("Fail to lock mutex for transceiver :{}", self.name));
+
+        locked_internal_state.contention_count
+    }
+
+    /// Tells the transceiver, that the byte on the bus during this tick is corrupted
+    /// by several drivers at once.
+    pub(crate) fn put_contention_to_device_network_side(&self, byte: u8) {
+        self.put_to_device_network_side(byte);
+
+        let mut locked_internal_state = self
+            .arc_mutexed_internal_state
+            .lock()
+            .unwrap_or_else(|_| panic!("Fail to lock mutex for transceiver :{}", self.name));
+
+        locked_internal_state.tick_contention = true;
+    }
+
+    pub fn read(&self, buf: &mut [u8]) -> Result<usize, core::convert::Infallible> {
+        let mut count_red: usize = 0;
+        for buf_vancant_place in buf.iter_mut() {
+            if let Some(byte) = self.get_from_tx_pin() {
+                *buf_vancant_place = byte;
+                count_red += 1;
+            }
+        }
+        Ok(count_red)
+    }
+
+    pub fn write(&self, buf: &[u8]) -> Result<usize, core::convert::Infallible> {
+        for b in buf {
+            self.put_to_rx_pin(*b);
+        }
+        Ok(buf.len())
+    }
+}
+
+impl Clone for Rs485TransceiverFake {
+    fn clone(&self) -> Self {
+        Rs485TransceiverFake {
+            arc_mutexed_internal_state: Arc::clone(&self.arc_mutexed_internal_state),
+            name: self.name.clone(),
+        }
+    }
+}
+
+impl IODriverSimulator for Rs485TransceiverFake {
+    /// Gets the byte driven on the bus during this tick
+    /// ```
+    /// use proto_lab::{IODriverSimulator, Rs485TransceiverFake};
+    ///
+    /// let transceiver = Rs485TransceiverFake::new("my_transceiver");
+    /// transceiver.put_to_rx_pin(1);
+    /// transceiver.put_to_rx_pin(2);
+    ///
+    /// transceiver.start_tick();
+    /// assert_eq!(transceiver.get_from_device_network_side(), None);
+    /// transceiver.end_tick();
+    ///
+    /// transceiver.set_driver_enable(true);
+    /// transceiver.start_tick();
+    /// assert_eq!(transceiver.get_from_device_network_side(), Some(2));
+    /// transceiver.end_tick();
+    /// ```
+    fn get_from_device_network_side(&self) -> Option<u8> {
+        let locked_internal_state = self
+            .arc_mutexed_internal_state
+            .lock()
+            .unwrap_or_else(|_| panic!("Fail to lock mutex for transceiver :{}", self.name));
+
+        match locked_internal_state.tick_state {
+            TickState::OffTick => panic!("Impossible to get_from_device_network_side. Device not in simulation mode. Simulation is within the tick. You shall start tick first."),
+            TickState::InTick => match locked_internal_state.driver_enabled {
+                true => locked_internal_state.shifting_byte,
+                false => None,
+            },
+        }
+    }
+
+    /// Puts the byte seen on the bus during this tick.
+    fn put_to_device_network_side(&self, byte: u8) {
+        let mut locked_internal_state = self
+            .arc_mutexed_internal_state
+            .lock()
+            .unwrap_or_else(|_| panic!("Fail to lock mutex for transceiver :{}", self.name));
+
+        match locked_internal_state.tick_state {
+            TickState::OffTick => panic!("Impossible to put_to_device_network_side. Device not in simulation mode. Simulation is within the tick. You shall start tick first."),
+            TickState::InTick => locked_internal_state.tick_line_byte = Some(byte),
+        }
+    }
+
+    fn get_from_tx_pin(&self) -> Option<u8> {
+        let mut locked_internal_state = self
+            .arc_mutexed_internal_state
+            .lock()
+            .unwrap_or_else(|_| panic!("Fail to lock mutex for transceiver :{}", self.name));
+
+        locked_internal_state.from_bus_buffer.pop_front()
+    }
+
+    fn put_to_rx_pin(&self, byte: u8) {
+        let mut locked_internal_state = self
+            .arc_mutexed_internal_state
+            .lock()
+            .unwrap_or_else(|_| panic!("Fail to lock mutex for transceiver :{}", self.name));
+
+        locked_internal_state.to_bus_buffer.push_back(byte);
+    }
+
+    fn start_tick(&self) {
+        let mut locked_internal_state = self
+            .arc_mutexed_internal_state
+            .lock()
+            .unwrap_or_else(|_| panic!("Fail to lock mutex for transceiver :{}", self.name));
+
+        if let TickState::OffTick = locked_internal_state.tick_state {
+            locked_internal_state.shifting_byte = locked_internal_state.to_bus_buffer.pop_front();
+            locked_internal_state.tick_line_byte = None;
+            locked_internal_state.tick_contention = false;
+            locked_internal_state.tick_state = TickState::InTick;
+        }
+    }
+
+    fn end_tick(&self) {
+        let mut locked_internal_state = self
+            .arc_mutexed_internal_state
+            .lock()
+            .unwrap_or_else(|_| panic!("Fail to lock mutex for transceiver :{}", self.name));
+
+        if let TickState::InTick = locked_internal_state.tick_state {
+            match locked_internal_state.tick_line_byte.take() {
+                Some(byte) => {
+                    locked_internal_state.idle_ticks = 0;
+                    if locked_internal_state.receiver_enabled {
+                        locked_internal_state.from_bus_buffer.push_back(byte);
+                        if locked_internal_state.tick_contention {
+                            locked_internal_state.contention_count += 1;
+                        }
+                    }
+                }
+                None => locked_internal_state.idle_ticks += 1,
+            }
+
+            locked_internal_state.shifting_byte = None;
+            locked_internal_state.tick_state = TickState::OffTick;
+        }
+    }
+
+    fn readable(&self) -> bool {
+        let locked_internal_state = self
+            .arc_mutexed_internal_state
+            .lock()
+            .unwrap_or_else(|_| panic!("Fail to lock mutex for transceiver :{}", self.name));
+
+        !locked_internal_state.from_bus_buffer.is_empty()
+    }
+
+    fn writable(&self) -> bool {
+        true
+    }
+
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl embedded_io::ErrorType for Rs485TransceiverFake {
+    type Error = core::convert::Infallible;
+}
+
+impl embedded_io::ReadReady for Rs485TransceiverFake {
+    fn read_ready(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.readable())
+    }
+}
+
+impl embedded_io::Read for Rs485TransceiverFake {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        Rs485TransceiverFake::read(self, buf)
+    }
+}
+
+impl embedded_io::Write for Rs485TransceiverFake {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        Rs485TransceiverFake::write(self, buf)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
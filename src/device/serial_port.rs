@@ -0,0 +1,261 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use crate::{uart::ReceivedByte, LineConfig};
+
+/// Error of the received frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SerialError {
+    /// Stop bit was not found, usually because of baud rate or frame format mismatch.
+    Framing,
+    /// Parity bit does not match the data bits.
+    Parity,
+    /// Line was held low for the whole frame.
+    Break,
+}
+
+impl core::fmt::Display for SerialError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SerialError::Framing => write!(f, "framing error"),
+            SerialError::Parity => write!(f, "parity error"),
+            SerialError::Break => write!(f, "break condition"),
+        }
+    }
+}
+
+impl std::error::Error for SerialError {}
+
+impl embedded_io::Error for SerialError {
+    fn kind(&self) -> embedded_io::ErrorKind {
+        embedded_io::ErrorKind::InvalidData
+    }
+}
+
+/// Symbol sent out to the line.
+#[derive(Clone, Copy)]
+pub(crate) enum LineSymbol {
+    Byte(u8),
+    Break,
+}
+
+struct InternalState {
+    to_line_buffer: VecDeque<LineSymbol>,
+    from_line_buffer: VecDeque<(u8, Option<SerialError>)>,
+    baud: u32,
+    line_config: LineConfig,
+    framing_error_count: u64,
+    parity_error_count: u64,
+    break_count: u64,
+}
+
+/// UART port, which is connected to the other one by `SerialLink`.
+/// Bytes written by the device are sent out with the speed of the port baud rate.
+/// Bytes sent by the peer with the other baud rate or line config are received garbled,
+/// with the error of their frame reported by `read`.
+pub struct SerialPortFake {
+    arc_mutexed_internal_state: Arc<Mutex<InternalState>>,
+    name: String,
+}
+
+impl SerialPortFake {
+    pub fn new(name: &str, baud: u32) -> Self {
+        if baud == 0 {
+            panic!("Baud rate of serial port :{} can not be 0", name);
+        }
+        SerialPortFake {
+            arc_mutexed_internal_state: Arc::new(Mutex::new(InternalState {
+                to_line_buffer: VecDeque::new(),
+                from_line_buffer: VecDeque::new(),
+                baud,
+                line_config: LineConfig::default(),
+                framing_error_count: 0,
+                parity_error_count: 0,
+                break_count: 0,
+            })),
+            name: String::from(name),
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    fn lock_internal_state(&self) -> std::sync::MutexGuard<'_, InternalState> {
+        self.arc_mutexed_internal_state
+            .lock()
+            .unwrap_or_else(|_| panic!("Fail to lock mutex for serial port :{}", self.name))
+    }
+
+    pub fn set_baud(&self, baud: u32) {
+        if baud == 0 {
+            panic!("Baud rate of serial port :{} can not be 0", self.name);
+        }
+        self.lock_internal_state().baud = baud;
+    }
+
+    pub fn get_baud(&self) -> u32 {
+        self.lock_internal_state().baud
+    }
+
+    /// Sets format of the frames, used for both sending and receiving.
+    /// Panics if count of data bits is not in range of 5 to 8.
+    pub fn set_line_config(&self, line_config: LineConfig) {
+        line_config.validate();
+        self.lock_internal_state().line_config = line_config;
+    }
+
+    pub fn get_line_config(&self) -> LineConfig {
+        self.lock_internal_state().line_config
+    }
+
+    /// Holds the line low for the duration of two frames, after the already written bytes.
+    pub fn send_break(&self) {
+        self.lock_internal_state()
+            .to_line_buffer
+            .push_back(LineSymbol::Break);
+    }
+
+    /// Gets count of received frames, which stop bit was not found.
+    pub fn get_framing_error_count(&self) -> u64 {
+        self.lock_internal_state().framing_error_count
+    }
+
+    /// Gets count of received frames with wrong parity bit.
+    pub fn get_parity_error_count(&self) -> u64 {
+        self.lock_internal_state().parity_error_count
+    }
+
+    /// Gets count of received break conditions.
+    pub fn get_break_count(&self) -> u64 {
+        self.lock_internal_state().break_count
+    }
+
+    /// Tells if all the written bytes are sent out.
+    pub fn is_transmission_complete(&self) -> bool {
+        self.lock_internal_state().to_line_buffer.is_empty()
+    }
+
+    pub fn readable(&self) -> bool {
+        !self.lock_internal_state().from_line_buffer.is_empty()
+    }
+
+    /// Reads the received bytes until the byte with the error.
+    /// Once such byte is first in the queue, its error is returned,
+    /// and the garbage byte itself is returned by the next read.
+    /// ```
+    /// use proto_lab::{LineConfig, NetworkSimulator, Parity, SerialError, SerialLink, SerialPortFake};
+    ///
+    /// let simulator = NetworkSimulator::new(1);
+    /// let a = SerialPortFake::new("a", 115200);
+    /// let b = SerialPortFake::new("b", 115200);
+    /// simulator.add_medium(SerialLink::new("cable", a.clone(), b.clone()));
+    ///
+    /// b.set_line_config(LineConfig { parity: Parity::Even, ..LineConfig::default() });
+    /// a.write(&[0x03]).unwrap();
+    /// a.send_break();
+    /// simulator.run_ticks(1);
+    ///
+    /// let mut buf = [0u8; 4];
+    /// assert_eq!(b.read(&mut buf), Err(SerialError::Parity));
+    /// assert_eq!(b.read(&mut buf), Ok(1));
+    /// assert_eq!(b.read(&mut buf), Err(SerialError::Break));
+    /// assert_eq!(b.read(&mut buf), Ok(1));
+    /// assert_eq!(buf[0], 0);
+    /// ```
+    pub fn read(&self, buf: &mut [u8]) -> Result<usize, SerialError> {
+        let mut locked_internal_state = self.lock_internal_state();
+        if let Some((_, error)) = locked_internal_state.from_line_buffer.front_mut() {
+            if let Some(error) = error.take() {
+                return Err(error);
+            }
+        }
+
+        let mut count_red: usize = 0;
+        for buf_vancant_place in buf.iter_mut() {
+            match locked_internal_state.from_line_buffer.front() {
+                Some((byte, None)) => {
+                    *buf_vancant_place = *byte;
+                    count_red += 1;
+                    locked_internal_state.from_line_buffer.pop_front();
+                }
+                _ => break,
+            }
+        }
+        Ok(count_red)
+    }
+
+    pub fn write(&self, buf: &[u8]) -> Result<usize, SerialError> {
+        self.lock_internal_state()
+            .to_line_buffer
+            .extend(buf.iter().map(|byte| LineSymbol::Byte(*byte)));
+        Ok(buf.len())
+    }
+
+    /// Takes the next symbol to be sent out.
+    pub(crate) fn take_from_line_side(&self) -> Option<LineSymbol> {
+        self.lock_internal_state().to_line_buffer.pop_front()
+    }
+
+    pub(crate) fn has_line_side_data(&self) -> bool {
+        !self.lock_internal_state().to_line_buffer.is_empty()
+    }
+
+    pub(crate) fn put_to_line_side(&self, received: ReceivedByte) {
+        let mut locked_internal_state = self.lock_internal_state();
+
+        let error = if received.break_condition {
+            locked_internal_state.break_count += 1;
+            Some(SerialError::Break)
+        } else if received.framing_error {
+            locked_internal_state.framing_error_count += 1;
+            Some(SerialError::Framing)
+        } else if received.parity_error {
+            locked_internal_state.parity_error_count += 1;
+            Some(SerialError::Parity)
+        } else {
+            None
+        };
+
+        locked_internal_state
+            .from_line_buffer
+            .push_back((received.byte, error));
+    }
+}
+
+impl Clone for SerialPortFake {
+    fn clone(&self) -> Self {
+        SerialPortFake {
+            arc_mutexed_internal_state: Arc::clone(&self.arc_mutexed_internal_state),
+            name: self.name.clone(),
+        }
+    }
+}
+
+impl embedded_io::ErrorType for SerialPortFake {
+    type Error = SerialError;
+}
+
+impl embedded_io::ReadReady for SerialPortFake {
+    fn read_ready(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.readable())
+    }
+}
+
+impl embedded_io::Read for SerialPortFake {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        SerialPortFake::read(self, buf)
+    }
+}
+
+impl embedded_io::Write for SerialPortFake {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        SerialPortFake::write(self, buf)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
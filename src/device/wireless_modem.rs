@@ -3,7 +3,12 @@ use std::{
     sync::{Arc, Mutex},
 };
 
-use super::IODriverSimulator;
+use crate::medium::DEFAULT_MS_PER_TICK;
+
+use super::{
+    energy::{EnergyMeter, EnergyState},
+    EnergyModel, EnergyReport, IODriverSimulator, ModePinFake, StatusPin, StatusPinFake,
+};
 
 enum AntennaState {
     Transmit(u8),
@@ -44,11 +49,57 @@ enum TickState {
     OffTick,
 }
 
+enum PowerState {
+    On,
+    Off,
+    Rebooting(u64),
+}
+
+/// Operating mode of the radio, which is selected from the device pins side,
+/// like with mode pins of E32 / LoRa UART modules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RadioMode {
+    /// Radio transmits and receives.
+    Normal,
+    /// Radio neither transmits nor receives. Bytes written into RX pin are kept
+    /// and transmitted once the radio is awake again.
+    Sleep,
+}
+
+enum RadioState {
+    Awake,
+    Sleeping,
+    WakingUp(u64),
+}
+
 struct InternalState {
     tick_state: TickState,
     from_antenna_buffer: VecDeque<u8>,
     to_antenna_buffer: VecDeque<u8>,
     antennta_state: AntennaState,
+    power_state: PowerState,
+    energy_meter: Option<EnergyMeter>,
+    radio_state: RadioState,
+    wake_up_latency_ticks: u64,
+    carrier_sensed: bool,
+    tx_pin_taps: Vec<Arc<Mutex<VecDeque<u8>>>>,
+    power_cycle_count: u64,
+    ms_per_tick: u64,
+}
+
+impl InternalState {
+    /// Powers the modem on, unless its battery is depleted, in which case it stays off.
+    fn power_up(&mut self) {
+        let is_depleted = self
+            .energy_meter
+            .as_ref()
+            .is_some_and(|energy_meter| energy_meter.get_report().is_depleted());
+
+        self.power_state = match is_depleted {
+            true => PowerState::Off,
+            false => PowerState::On,
+        };
+    }
 }
 
 impl embedded_io::ErrorType for WirelessModemFake {
@@ -63,23 +114,26 @@ impl embedded_io::ReadReady for WirelessModemFake {
 
 impl embedded_io::Read for WirelessModemFake {
     fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
-        WirelessModemFake::read(&self, buf)
+        WirelessModemFake::read(self, buf)
     }
 }
 
 impl embedded_io::Write for WirelessModemFake {
     fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
-        WirelessModemFake::write(&self, buf)
+        WirelessModemFake::write(self, buf)
     }
 
     fn flush(&mut self) -> Result<(), Self::Error> {
-        WirelessModemFake::flush(&self)
+        WirelessModemFake::flush(self)
     }
 }
 
 pub struct WirelessModemFake {
     arc_mutexed_internal_state: Arc<Mutex<InternalState>>,
     name: String,
+    /// Span of the device, shared by all clones of the modem.
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
 }
 
 impl WirelessModemFake {
@@ -90,8 +144,18 @@ impl WirelessModemFake {
                 from_antenna_buffer: VecDeque::new(),
                 to_antenna_buffer: VecDeque::new(),
                 antennta_state: AntennaState::Idle,
+                power_state: PowerState::On,
+                energy_meter: None,
+                radio_state: RadioState::Awake,
+                wake_up_latency_ticks: 0,
+                carrier_sensed: false,
+                tx_pin_taps: Vec::new(),
+                power_cycle_count: 0,
+                ms_per_tick: DEFAULT_MS_PER_TICK,
             })),
             name: String::from(name),
+            #[cfg(feature = "tracing")]
+            span: tracing::debug_span!(parent: None, "device", name),
         }
     }
 
@@ -119,10 +183,355 @@ impl WirelessModemFake {
         Ok(())
     }
 
-    pub fn clone(&self) -> Self {
+    /// Powers the modem off. It stops transmitting and receiving immediately,
+    /// and ignores everything written into its RX pin until powered on again.
+    /// Bytes, which are not transmitted or not read yet, are kept unless `lose_buffers` is set.
+    /// ```
+    /// use proto_lab::{IODriverSimulator, WirelessModemFake};
+    ///
+    /// let device = WirelessModemFake::new("my_modem");
+    /// device.put_to_rx_pin(1);
+    /// device.power_off(false);
+    ///
+    /// device.start_tick();
+    /// assert_eq!(device.get_from_device_network_side(), None);
+    /// device.end_tick();
+    ///
+    /// device.power_on();
+    /// device.start_tick();
+    /// assert_eq!(device.get_from_device_network_side(), Some(1));
+    /// device.end_tick();
+    /// ```
+    pub fn power_off(&self, lose_buffers: bool) {
+        let mut locked_internal_state = self
+            .arc_mutexed_internal_state
+            .lock()
+            .unwrap_or_else(|_| panic!("Fail to lock mutex for modem :{}", self.name));
+
+        if let PowerState::On = locked_internal_state.power_state {
+            locked_internal_state.power_cycle_count += 1;
+        }
+        locked_internal_state.power_state = PowerState::Off;
+        locked_internal_state.antennta_state = AntennaState::Idle;
+
+        if lose_buffers {
+            locked_internal_state.from_antenna_buffer.clear();
+            locked_internal_state.to_antenna_buffer.clear();
+        }
+    }
+
+    /// Powers the modem on. Takes effect immediately, even during the tick.
+    /// The modem with depleted battery stays powered off.
+    pub fn power_on(&self) {
+        let mut locked_internal_state = self
+            .arc_mutexed_internal_state
+            .lock()
+            .unwrap_or_else(|_| panic!("Fail to lock mutex for modem :{}", self.name));
+
+        locked_internal_state.power_up();
+    }
+
+    /// Simulates crash of the device: it is powered off immediately,
+    /// dropping the byte in the air and all the buffered bytes.
+    pub fn crash(&self) {
+        self.power_off(true);
+    }
+
+    /// Crashes the device and powers it on again after `delay_ticks` powered off ticks.
+    /// ```
+    /// use proto_lab::{IODriverSimulator, WirelessModemFake};
+    ///
+    /// let device = WirelessModemFake::new("my_modem");
+    /// device.reboot(2);
+    ///
+    /// device.start_tick();
+    /// device.end_tick();
+    /// assert!(!device.is_powered());
+    ///
+    /// device.start_tick();
+    /// device.end_tick();
+    /// assert!(device.is_powered());
+    /// ```
+    pub fn reboot(&self, delay_ticks: u64) {
+        self.crash();
+
+        let mut locked_internal_state = self
+            .arc_mutexed_internal_state
+            .lock()
+            .unwrap_or_else(|_| panic!("Fail to lock mutex for modem :{}", self.name));
+
+        match delay_ticks {
+            0 => locked_internal_state.power_up(),
+            _ => locked_internal_state.power_state = PowerState::Rebooting(delay_ticks),
+        }
+    }
+
+    /// Tells if the modem is powered on.
+    pub fn is_powered(&self) -> bool {
+        let locked_internal_state = self
+            .arc_mutexed_internal_state
+            .lock()
+            .unwrap_or_else(|_| panic!("Fail to lock mutex for modem :{}", self.name));
+
+        matches!(locked_internal_state.power_state, PowerState::On)
+    }
+
+    /// Counts the times the modem has lost power, by power off, crash, reboot or
+    /// depleted battery, even if it was powered on again in between of two checks.
+    /// ```
+    /// use proto_lab::WirelessModemFake;
+    ///
+    /// let device = WirelessModemFake::new("my_modem");
+    /// device.reboot(0);
+    /// assert!(device.is_powered());
+    /// assert_eq!(device.get_power_cycle_count(), 1);
+    /// ```
+    pub fn get_power_cycle_count(&self) -> u64 {
+        let locked_internal_state = self
+            .arc_mutexed_internal_state
+            .lock()
+            .unwrap_or_else(|_| panic!("Fail to lock mutex for modem :{}", self.name));
+
+        locked_internal_state.power_cycle_count
+    }
+
+    /// Selects the mode of the radio from the device pins side.
+    /// Radio goes to sleep immediately, but waking up takes the wake up latency.
+    /// ```
+    /// use proto_lab::{IODriverSimulator, RadioMode, WirelessModemFake};
+    ///
+    /// let device = WirelessModemFake::new("my_modem");
+    /// device.set_wake_up_latency(1);
+    /// device.set_mode(RadioMode::Sleep);
+    /// device.put_to_rx_pin(1);
+    ///
+    /// device.start_tick();
+    /// assert_eq!(device.get_from_device_network_side(), None);
+    /// device.end_tick();
+    ///
+    /// device.set_mode(RadioMode::Normal);
+    /// assert!(!device.is_radio_awake());
+    ///
+    /// device.start_tick();
+    /// assert_eq!(device.get_from_device_network_side(), None);
+    /// device.end_tick();
+    ///
+    /// device.start_tick();
+    /// assert_eq!(device.get_from_device_network_side(), Some(1));
+    /// device.end_tick();
+    /// ```
+    pub fn set_mode(&self, mode: RadioMode) {
+        let mut locked_internal_state = self
+            .arc_mutexed_internal_state
+            .lock()
+            .unwrap_or_else(|_| panic!("Fail to lock mutex for modem :{}", self.name));
+
+        locked_internal_state.radio_state = match (mode, &locked_internal_state.radio_state) {
+            (RadioMode::Sleep, _) => {
+                locked_internal_state.antennta_state = AntennaState::Idle;
+                RadioState::Sleeping
+            }
+            (RadioMode::Normal, RadioState::Sleeping) => {
+                match locked_internal_state.wake_up_latency_ticks {
+                    0 => RadioState::Awake,
+                    latency => RadioState::WakingUp(latency),
+                }
+            }
+            (RadioMode::Normal, RadioState::Awake) => RadioState::Awake,
+            (RadioMode::Normal, RadioState::WakingUp(ticks_left)) => {
+                RadioState::WakingUp(*ticks_left)
+            }
+        };
+    }
+
+    /// Gets the mode of the radio selected from the device pins side.
+    pub fn get_mode(&self) -> RadioMode {
+        let locked_internal_state = self
+            .arc_mutexed_internal_state
+            .lock()
+            .unwrap_or_else(|_| panic!("Fail to lock mutex for modem :{}", self.name));
+
+        match locked_internal_state.radio_state {
+            RadioState::Sleeping => RadioMode::Sleep,
+            RadioState::Awake | RadioState::WakingUp(_) => RadioMode::Normal,
+        }
+    }
+
+    /// Sets count of ticks the radio needs to wake up.
+    pub fn set_wake_up_latency(&self, ticks: u64) {
+        let mut locked_internal_state = self
+            .arc_mutexed_internal_state
+            .lock()
+            .unwrap_or_else(|_| panic!("Fail to lock mutex for modem :{}", self.name));
+
+        locked_internal_state.wake_up_latency_ticks = ticks;
+    }
+
+    /// Tells if the radio is awake and able to transmit and receive.
+    pub fn is_radio_awake(&self) -> bool {
+        let locked_internal_state = self
+            .arc_mutexed_internal_state
+            .lock()
+            .unwrap_or_else(|_| panic!("Fail to lock mutex for modem :{}", self.name));
+
+        matches!(locked_internal_state.radio_state, RadioState::Awake)
+    }
+
+    /// Tells if the modem is busy and can not transmit right away: it has bytes to transmit,
+    /// transmits right now, its radio is not awake, or it is powered off.
+    /// That is what AUX / BUSY pin of UART radio modules shows.
+    /// ```
+    /// use proto_lab::{IODriverSimulator, WirelessModemFake};
+    ///
+    /// let device = WirelessModemFake::new("my_modem");
+    /// assert!(!device.is_busy());
+    /// device.put_to_rx_pin(1);
+    /// assert!(device.is_busy());
+    /// ```
+    pub fn is_busy(&self) -> bool {
+        let locked_internal_state = self
+            .arc_mutexed_internal_state
+            .lock()
+            .unwrap_or_else(|_| panic!("Fail to lock mutex for modem :{}", self.name));
+
+        !matches!(locked_internal_state.power_state, PowerState::On)
+            || !matches!(locked_internal_state.radio_state, RadioState::Awake)
+            || matches!(
+                locked_internal_state.antennta_state,
+                AntennaState::Transmit(_)
+            )
+            || !locked_internal_state.to_antenna_buffer.is_empty()
+    }
+
+    /// Tells if the radio caught a byte from the ether during the last tick,
+    /// so the channel is busy with someone else's transmission.
+    /// ```
+    /// use proto_lab::{IODriverSimulator, WirelessModemFake};
+    ///
+    /// let device = WirelessModemFake::new("my_modem");
+    /// device.start_tick();
+    /// device.put_to_device_network_side(1);
+    /// device.end_tick();
+    /// assert!(device.is_receiving());
+    ///
+    /// device.start_tick();
+    /// device.end_tick();
+    /// assert!(!device.is_receiving());
+    /// ```
+    pub fn is_receiving(&self) -> bool {
+        let locked_internal_state = self
+            .arc_mutexed_internal_state
+            .lock()
+            .unwrap_or_else(|_| panic!("Fail to lock mutex for modem :{}", self.name));
+
+        locked_internal_state.carrier_sensed
+    }
+
+    /// Gets the status pin of the modem, which can be polled by the firmware.
+    pub fn get_status_pin(&self, pin: StatusPin) -> StatusPinFake {
+        StatusPinFake::new(self.clone(), pin)
+    }
+
+    /// Gets the mode select pin of the modem, which can be driven by the firmware.
+    pub fn get_mode_pin(&self) -> ModePinFake {
+        ModePinFake::new(self.clone())
+    }
+
+    /// Starts accounting of energy consumed by the modem with the model.
+    /// Every powered on tick consumes the current of the state the antenna was in during that tick.
+    /// Tick duration is the one of the simulator, which owns the ethers the modem is registered in.
+    /// Setting the model again starts accounting from scratch with a fresh battery.
+    /// ```
+    /// use proto_lab::{EnergyModel, IODriverSimulator, NetworkSimulator, WirelessModemFake};
+    ///
+    /// let simulator = NetworkSimulator::new(1000);
+    /// simulator.create_ether("air");
+    /// let device = WirelessModemFake::new("my_modem");
+    /// simulator.get_ether("air").unwrap().register_driver(device.clone());
+    /// device.set_energy_model(EnergyModel {
+    ///     voltage_v: 3.0,
+    ///     tx_current_ma: 100.0,
+    ///     rx_current_ma: 10.0,
+    ///     idle_current_ma: 1.0,
+    ///     sleep_current_ma: 0.0,
+    ///     battery_capacity_mah: None,
+    /// });
+    ///
+    /// device.put_to_rx_pin(1);
+    /// simulator.run_ticks(2);
+    ///
+    /// let report = device.get_energy_report().unwrap();
+    /// assert_eq!((report.tx_ticks, report.idle_ticks), (1, 1));
+    /// assert_eq!(report.consumed_mj, 303.0);
+    /// ```
+    pub fn set_energy_model(&self, model: EnergyModel) {
+        let mut locked_internal_state = self
+            .arc_mutexed_internal_state
+            .lock()
+            .unwrap_or_else(|_| panic!("Fail to lock mutex for modem :{}", self.name));
+
+        locked_internal_state.energy_meter = Some(EnergyMeter::new(model));
+    }
+
+    /// Sets duration of the tick used for energy accounting.
+    /// Is set by the ethers of the simulator, the modem is registered in,
+    /// and by the simulator, when an application is registered with the modem.
+    pub(crate) fn set_ms_per_tick(&self, ms_per_tick: u64) {
+        let mut locked_internal_state = self
+            .arc_mutexed_internal_state
+            .lock()
+            .unwrap_or_else(|_| panic!("Fail to lock mutex for modem :{}", self.name));
+
+        locked_internal_state.ms_per_tick = ms_per_tick;
+    }
+
+    /// Gets energy consumed so far. `None` if no energy model is set.
+    pub fn get_energy_report(&self) -> Option<EnergyReport> {
+        let locked_internal_state = self
+            .arc_mutexed_internal_state
+            .lock()
+            .unwrap_or_else(|_| panic!("Fail to lock mutex for modem :{}", self.name));
+
+        locked_internal_state
+            .energy_meter
+            .as_ref()
+            .map(|energy_meter| energy_meter.get_report())
+    }
+
+    /// Attaches the tap, which gets a copy of every byte put out on the TX pin,
+    /// while the bytes are still read from the pin as usual.
+    pub(crate) fn add_tx_pin_tap(&self, tap: Arc<Mutex<VecDeque<u8>>>) {
+        let mut locked_internal_state = self
+            .arc_mutexed_internal_state
+            .lock()
+            .unwrap_or_else(|_| panic!("Fail to lock mutex for modem :{}", self.name));
+
+        locked_internal_state.tx_pin_taps.push(tap);
+    }
+
+    /// Gets the span of the device, which is created once together with the modem,
+    /// so install the subscriber before creating modems.
+    /// Enter it while updating the protocol code of the device to get its logs
+    /// grouped together with bytes moved by the simulator for that device.
+    /// ```
+    /// use proto_lab::WirelessModemFake;
+    ///
+    /// let device = WirelessModemFake::new("my_modem");
+    /// let _device_span = device.span().entered();
+    /// ```
+    #[cfg(feature = "tracing")]
+    pub fn span(&self) -> tracing::Span {
+        self.span.clone()
+    }
+}
+
+impl Clone for WirelessModemFake {
+    fn clone(&self) -> Self {
         WirelessModemFake {
             arc_mutexed_internal_state: Arc::clone(&self.arc_mutexed_internal_state),
             name: self.name.clone(),
+            #[cfg(feature = "tracing")]
+            span: self.span.clone(),
         }
     }
 }
@@ -130,8 +539,8 @@ impl WirelessModemFake {
 impl IODriverSimulator for WirelessModemFake {
     /// Simulates that the modem emits a byte towards the ether
     /// ```
-    /// use network_simulator::WirelessModemFake;
-    /// use network_simulator::IODriverSimulator;
+    /// use proto_lab::WirelessModemFake;
+    /// use proto_lab::IODriverSimulator;
     ///
     /// let device = WirelessModemFake::new("my_modem");
     /// device.start_tick();
@@ -144,12 +553,11 @@ impl IODriverSimulator for WirelessModemFake {
     /// assert_eq!(device.get_from_device_network_side(), Some(1));
     /// device.end_tick();
     /// ```
-
     fn get_from_device_network_side(&self) -> Option<u8> {
         let locked_internal_state = self
             .arc_mutexed_internal_state
             .lock()
-            .expect(format!("Fail to lock mutex for modem :{}", self.name).as_str());
+            .unwrap_or_else(|_| panic!("Fail to lock mutex for modem :{}", self.name));
 
         match locked_internal_state.tick_state {
             TickState::OffTick => panic!("Impossible to put_to_device_network_side. Device not in simulation mode. Simulation is within the tick. You shall start tick first."),
@@ -162,8 +570,8 @@ impl IODriverSimulator for WirelessModemFake {
 
     /// Simulates that the modem caught a byte from the ether
     /// ```
-    /// use network_simulator::WirelessModemFake;
-    /// use network_simulator::IODriverSimulator;
+    /// use proto_lab::WirelessModemFake;
+    /// use proto_lab::IODriverSimulator;
     ///
     /// let device = WirelessModemFake::new("my_modem");
     /// assert_eq!(device.get_from_tx_pin(), None);
@@ -176,23 +584,30 @@ impl IODriverSimulator for WirelessModemFake {
         let mut locked_internal_state = self
             .arc_mutexed_internal_state
             .lock()
-            .expect(format!("Fail to lock mutex for modem :{}", self.name).as_str());
+            .unwrap_or_else(|_| panic!("Fail to lock mutex for modem :{}", self.name));
 
         match locked_internal_state.tick_state {
             TickState::OffTick => panic!("Impossible to put_to_device_network_side. Device not in simulation mode. Simulation is within the tick. You shall start tick first."),
-            TickState::InTick => match locked_internal_state.antennta_state {
-                AntennaState::Transmit(_) => (),
-                AntennaState::Idle | AntennaState::Receive(_) => {
+            TickState::InTick => {
+                if let (
+                    PowerState::On,
+                    RadioState::Awake,
+                    AntennaState::Idle | AntennaState::Receive(_),
+                ) = (
+                    &locked_internal_state.power_state,
+                    &locked_internal_state.radio_state,
+                    &locked_internal_state.antennta_state,
+                ) {
                     locked_internal_state.antennta_state = AntennaState::Receive(byte)
                 }
-            },
+            }
         }
     }
 
     /// Reads a byte on the TX pin
     /// ```
-    /// use network_simulator::WirelessModemFake;
-    /// use network_simulator::IODriverSimulator;
+    /// use proto_lab::WirelessModemFake;
+    /// use proto_lab::IODriverSimulator;
     ///
     /// let device = WirelessModemFake::new("my_modem");
     /// assert_eq!(device.get_from_tx_pin(), None);
@@ -205,15 +620,18 @@ impl IODriverSimulator for WirelessModemFake {
         let mut locked_internal_state = self
             .arc_mutexed_internal_state
             .lock()
-            .expect(format!("Fail to lock mutex for modem :{}", self.name).as_str());
+            .unwrap_or_else(|_| panic!("Fail to lock mutex for modem :{}", self.name));
 
-        locked_internal_state.from_antenna_buffer.pop_front()
+        match locked_internal_state.power_state {
+            PowerState::On => locked_internal_state.from_antenna_buffer.pop_front(),
+            _ => None,
+        }
     }
 
     /// Writes a byte on the RX pin
     /// ```
-    /// use network_simulator::WirelessModemFake;
-    /// use network_simulator::IODriverSimulator;
+    /// use proto_lab::WirelessModemFake;
+    /// use proto_lab::IODriverSimulator;
     ///
     /// let device = WirelessModemFake::new("my_modem");
     /// device.put_to_rx_pin(1);
@@ -224,9 +642,11 @@ impl IODriverSimulator for WirelessModemFake {
         let mut locked_internal_state = self
             .arc_mutexed_internal_state
             .lock()
-            .expect(format!("Fail to lock mutex for modem :{}", self.name).as_str());
+            .unwrap_or_else(|_| panic!("Fail to lock mutex for modem :{}", self.name));
 
-        locked_internal_state.to_antenna_buffer.push_back(byte);
+        if let PowerState::On = locked_internal_state.power_state {
+            locked_internal_state.to_antenna_buffer.push_back(byte);
+        }
     }
 
     /// Tick is needed only for simulating time during which ineraction with the ether is going.
@@ -235,15 +655,22 @@ impl IODriverSimulator for WirelessModemFake {
         let mut locked_internal_state = self
             .arc_mutexed_internal_state
             .lock()
-            .expect(format!("Fail to lock mutex for modem :{}", self.name).as_str());
+            .unwrap_or_else(|_| panic!("Fail to lock mutex for modem :{}", self.name));
 
         match locked_internal_state.tick_state {
             TickState::OffTick => {
-                locked_internal_state.antennta_state =
-                    match locked_internal_state.to_antenna_buffer.pop_front() {
-                        Some(byte) => AntennaState::Transmit(byte),
-                        _ => AntennaState::Idle,
-                    };
+                locked_internal_state.antennta_state = match (
+                    &locked_internal_state.power_state,
+                    &locked_internal_state.radio_state,
+                ) {
+                    (PowerState::On, RadioState::Awake) => {
+                        match locked_internal_state.to_antenna_buffer.pop_front() {
+                            Some(byte) => AntennaState::Transmit(byte),
+                            _ => AntennaState::Idle,
+                        }
+                    }
+                    _ => AntennaState::Idle,
+                };
 
                 locked_internal_state.tick_state = TickState::InTick;
             }
@@ -257,20 +684,69 @@ impl IODriverSimulator for WirelessModemFake {
         let mut locked_internal_state = self
             .arc_mutexed_internal_state
             .lock()
-            .expect(format!("Fail to lock mutex for modem :{}", self.name).as_str());
+            .unwrap_or_else(|_| panic!("Fail to lock mutex for modem :{}", self.name));
 
         match locked_internal_state.tick_state {
             TickState::OffTick => (),
             TickState::InTick => {
-                match locked_internal_state.antennta_state {
-                    AntennaState::Receive(byte) => {
-                        locked_internal_state.from_antenna_buffer.push_back(byte);
+                if let AntennaState::Receive(byte) = locked_internal_state.antennta_state {
+                    locked_internal_state.from_antenna_buffer.push_back(byte);
+                    for tap in locked_internal_state.tx_pin_taps.iter() {
+                        tap.lock().expect("Fail to lock TX pin tap").push_back(byte);
+                    }
+                }
+                locked_internal_state.carrier_sensed = matches!(
+                    locked_internal_state.antennta_state,
+                    AntennaState::Receive(_)
+                );
+
+                if let PowerState::On = locked_internal_state.power_state {
+                    let energy_state = match (
+                        &locked_internal_state.radio_state,
+                        &locked_internal_state.antennta_state,
+                    ) {
+                        (RadioState::Sleeping, _) => EnergyState::Sleep,
+                        (_, AntennaState::Transmit(_)) => EnergyState::Transmit,
+                        (_, AntennaState::Receive(_)) => EnergyState::Receive,
+                        (_, AntennaState::Idle) => EnergyState::Idle,
+                    };
+
+                    let ms_per_tick = locked_internal_state.ms_per_tick;
+                    let is_depleted = match locked_internal_state.energy_meter {
+                        Some(ref mut energy_meter) => {
+                            energy_meter.consume_tick(energy_state, ms_per_tick);
+                            energy_meter.get_report().is_depleted()
+                        }
+                        None => false,
+                    };
+
+                    if is_depleted {
+                        locked_internal_state.power_cycle_count += 1;
+                        locked_internal_state.power_state = PowerState::Off;
+                        locked_internal_state.from_antenna_buffer.clear();
+                        locked_internal_state.to_antenna_buffer.clear();
                     }
-                    _ => (),
                 }
 
                 locked_internal_state.antennta_state = AntennaState::Idle;
 
+                if let RadioState::WakingUp(ticks_left) = locked_internal_state.radio_state {
+                    locked_internal_state.radio_state = match ticks_left {
+                        0 | 1 => RadioState::Awake,
+                        _ => RadioState::WakingUp(ticks_left - 1),
+                    };
+                }
+
+                if let PowerState::Rebooting(ticks_left) = locked_internal_state.power_state {
+                    match ticks_left {
+                        0 | 1 => locked_internal_state.power_up(),
+                        _ => {
+                            locked_internal_state.power_state =
+                                PowerState::Rebooting(ticks_left - 1)
+                        }
+                    }
+                }
+
                 locked_internal_state.tick_state = TickState::OffTick;
             }
         }
@@ -278,8 +754,8 @@ impl IODriverSimulator for WirelessModemFake {
 
     /// Tells if the device has some bytes to be red from pin
     /// ```
-    /// use network_simulator::WirelessModemFake;
-    /// use network_simulator::IODriverSimulator;
+    /// use proto_lab::WirelessModemFake;
+    /// use proto_lab::IODriverSimulator;
     /// let mut device = WirelessModemFake::new("");
     /// assert!(
     /// !device.readable());
@@ -292,25 +768,29 @@ impl IODriverSimulator for WirelessModemFake {
         let locked_internal_state = self
             .arc_mutexed_internal_state
             .lock()
-            .expect(format!("Fail to lock mutex for modem :{}", self.name).as_str());
+            .unwrap_or_else(|_| panic!("Fail to lock mutex for modem :{}", self.name));
 
-        !locked_internal_state.from_antenna_buffer.is_empty()
+        matches!(locked_internal_state.power_state, PowerState::On)
+            && !locked_internal_state.from_antenna_buffer.is_empty()
     }
 
     /// Tells if the device is ready to be written in
     /// ```
-    /// use network_simulator::WirelessModemFake;
-    /// use network_simulator::IODriverSimulator;
-    /// assert!(WirelessModemFake::new("").writable());
+    /// use proto_lab::WirelessModemFake;
+    /// use proto_lab::IODriverSimulator;
+    /// let device = WirelessModemFake::new("");
+    /// assert!(device.writable());
+    /// device.power_off(false);
+    /// assert!(!device.writable());
     /// ```
     fn writable(&self) -> bool {
-        true
+        self.is_powered()
     }
 
     /// Returns the name of the device
     /// ```
-    /// use network_simulator::WirelessModemFake;
-    /// use network_simulator::IODriverSimulator;
+    /// use proto_lab::WirelessModemFake;
+    /// use proto_lab::IODriverSimulator;
     /// let device = WirelessModemFake::new("my_modem");
     /// assert_eq!(device.get_name(), "my_modem");
     /// ```
@@ -350,4 +830,126 @@ mod radio_modem_device_tests {
         modem_device.end_tick();
         assert_eq!(modem_device.get_from_tx_pin(), Some(b'c'));
     }
+
+    // Test depleted battery powers the modem off for good
+    #[test]
+    fn test_battery_depletion() {
+        let modem_device = WirelessModemFake::new("");
+        modem_device.set_ms_per_tick(1000);
+        modem_device.set_energy_model(EnergyModel {
+            voltage_v: 3.3,
+            tx_current_ma: 3600.0,
+            rx_current_ma: 3600.0,
+            idle_current_ma: 3600.0,
+            sleep_current_ma: 0.0,
+            battery_capacity_mah: Some(2.0),
+        });
+
+        modem_device.start_tick();
+        modem_device.end_tick();
+        assert!(modem_device.is_powered());
+
+        modem_device.start_tick();
+        modem_device.end_tick();
+        assert!(!modem_device.is_powered());
+
+        modem_device.power_on();
+        assert!(!modem_device.is_powered());
+        assert!(modem_device.get_energy_report().unwrap().is_depleted());
+    }
+
+    // Test depleted modem stays off whatever way it is rebooted
+    #[test]
+    fn test_depleted_modem_does_not_reboot() {
+        let modem_device = WirelessModemFake::new("");
+        modem_device.set_ms_per_tick(1000);
+        modem_device.set_energy_model(EnergyModel {
+            voltage_v: 3.3,
+            tx_current_ma: 3600.0,
+            rx_current_ma: 3600.0,
+            idle_current_ma: 3600.0,
+            sleep_current_ma: 0.0,
+            battery_capacity_mah: Some(1.0),
+        });
+        modem_device.start_tick();
+        modem_device.end_tick();
+        let consumed_mj = modem_device.get_energy_report().unwrap().consumed_mj;
+
+        modem_device.reboot(0);
+        assert!(!modem_device.is_powered());
+
+        modem_device.reboot(2);
+        for _ in 0..3 {
+            modem_device.start_tick();
+            modem_device.end_tick();
+        }
+        assert!(!modem_device.is_powered());
+        assert_eq!(
+            modem_device.get_energy_report().unwrap().consumed_mj,
+            consumed_mj
+        );
+    }
+
+    // Test modem registered only with an application uses tick duration of the simulator
+    #[test]
+    fn test_application_modem_uses_tick_duration_of_simulator() {
+        let simulator = crate::NetworkSimulator::new(1000);
+        let modem_device = WirelessModemFake::new("");
+        modem_device.set_energy_model(EnergyModel {
+            voltage_v: 1.0,
+            tx_current_ma: 0.0,
+            rx_current_ma: 0.0,
+            idle_current_ma: 1.0,
+            sleep_current_ma: 0.0,
+            battery_capacity_mah: None,
+        });
+        simulator
+            .register_application(modem_device.clone(), |_: &mut WirelessModemFake, _: u64| {});
+
+        modem_device.start_tick();
+        modem_device.end_tick();
+        assert_eq!(modem_device.get_energy_report().unwrap().consumed_mj, 1.0);
+    }
+
+    // Test sleeping radio does not receive and consumes sleep current
+    #[test]
+    fn test_sleeping_radio_does_not_receive() {
+        let modem_device = WirelessModemFake::new("");
+        modem_device.set_energy_model(EnergyModel {
+            voltage_v: 3.3,
+            tx_current_ma: 100.0,
+            rx_current_ma: 10.0,
+            idle_current_ma: 10.0,
+            sleep_current_ma: 0.01,
+            battery_capacity_mah: None,
+        });
+        modem_device.set_mode(RadioMode::Sleep);
+
+        modem_device.start_tick();
+        modem_device.put_to_device_network_side(b'a');
+        modem_device.end_tick();
+
+        assert_eq!(modem_device.get_from_tx_pin(), None);
+        assert_eq!(modem_device.get_energy_report().unwrap().sleep_ticks, 1);
+    }
+
+    // Test crash in the middle of transmission drops the byte in the air and the rest of data
+    #[test]
+    fn test_crash_mid_transmission() {
+        let modem_device = WirelessModemFake::new("");
+        modem_device.put_to_rx_pin(b'a');
+        modem_device.put_to_rx_pin(b'b');
+
+        modem_device.start_tick();
+        modem_device.crash();
+        assert_eq!(modem_device.get_from_device_network_side(), None);
+        modem_device.put_to_device_network_side(b'c');
+        modem_device.end_tick();
+
+        modem_device.power_on();
+        modem_device.start_tick();
+        assert_eq!(modem_device.get_from_device_network_side(), None);
+        modem_device.end_tick();
+        assert_eq!(modem_device.get_from_tx_pin(), None);
+    }
 }
@@ -1,10 +1,13 @@
 use std::sync::{Arc, Mutex};
 
-use crate::{device::IODriverSimulator, WirelessModemFake};
+use crate::{device::IODriverSimulator, MediumSimulator, WirelessModemFake};
 
 pub struct EtherSimulator {
     name: String,
     devices: Arc<Mutex<Vec<WirelessModemFake>>>,
+    collision_count: Arc<Mutex<u64>>,
+    /// Tick duration of the simulator, which owns the ether.
+    ms_per_tick: Arc<Mutex<Option<u64>>>,
 }
 
 impl EtherSimulator {
@@ -12,12 +15,14 @@ impl EtherSimulator {
         Self {
             name: String::from(name),
             devices: Arc::new(Mutex::new(vec![])),
+            collision_count: Arc::new(Mutex::new(0)),
+            ms_per_tick: Arc::new(Mutex::new(None)),
         }
     }
 
     /// Gets the name of the ether
     /// ```
-    /// use network_simulator::EtherSimulator;
+    /// use proto_lab::EtherSimulator;
     /// let mut ether = EtherSimulator::new("my_ether");
     /// assert_eq!(ether.get_name(), "my_ether");
     /// ```
@@ -27,24 +32,46 @@ impl EtherSimulator {
 
     /// Registers a new device (driver / modem)
     /// ```
-    /// use network_simulator::EtherSimulator;
-    /// use network_simulator::WirelessModemFake;
-    /// use network_simulator::IODriverSimulator;
+    /// use proto_lab::EtherSimulator;
+    /// use proto_lab::WirelessModemFake;
+    /// use proto_lab::IODriverSimulator;
     ///
     /// let mut ether = EtherSimulator::new("my_ether");
     /// ether.register_driver(WirelessModemFake::new("my_modem"));
     /// assert_eq!(ether.get_driver("my_modem").unwrap().get_name(), "my_modem");
     /// ```
     pub fn register_driver(&mut self, driver: WirelessModemFake) {
+        if let Some(ms_per_tick) = *self
+            .ms_per_tick
+            .lock()
+            .expect("Fail to get lock on tick duration")
+        {
+            driver.set_ms_per_tick(ms_per_tick);
+        }
         let mut devices = self.devices.lock().expect("Fail to get lock on devices");
         devices.push(WirelessModemFake::clone(&driver));
     }
 
+    /// Registers the device at the position in the order of registration,
+    /// which decides the device winning a collision. Position past the end registers it last.
+    pub(crate) fn insert_driver(&mut self, index: usize, driver: WirelessModemFake) {
+        if let Some(ms_per_tick) = *self
+            .ms_per_tick
+            .lock()
+            .expect("Fail to get lock on tick duration")
+        {
+            driver.set_ms_per_tick(ms_per_tick);
+        }
+        let mut devices = self.devices.lock().expect("Fail to get lock on devices");
+        let index = index.min(devices.len());
+        devices.insert(index, driver);
+    }
+
     /// Unregisters a device
     /// ```
-    /// use network_simulator::EtherSimulator;
-    /// use network_simulator::WirelessModemFake;
-    /// use network_simulator::IODriverSimulator;
+    /// use proto_lab::EtherSimulator;
+    /// use proto_lab::WirelessModemFake;
+    /// use proto_lab::IODriverSimulator;
     ///
     /// let mut ether = EtherSimulator::new("my_ether");
     /// ether.register_driver(WirelessModemFake::new("my_modem"));
@@ -72,9 +99,9 @@ impl EtherSimulator {
 
     /// Gets a registered device
     /// ```
-    /// use network_simulator::EtherSimulator;
-    /// use network_simulator::WirelessModemFake;
-    /// use network_simulator::IODriverSimulator;
+    /// use proto_lab::EtherSimulator;
+    /// use proto_lab::WirelessModemFake;
+    /// use proto_lab::IODriverSimulator;
     ///
     /// let mut ether = EtherSimulator::new("my_ether");
     /// assert!(ether.get_driver("my_modem").is_none());
@@ -86,24 +113,80 @@ impl EtherSimulator {
 
         for device in devices.iter() {
             if device.get_name() == name {
-                return Some(WirelessModemFake::clone(&device));
+                return Some(WirelessModemFake::clone(device));
             }
         }
         None
     }
 
+    /// Gets names of all registered devices in order of registration
+    /// ```
+    /// use proto_lab::EtherSimulator;
+    /// use proto_lab::WirelessModemFake;
+    ///
+    /// let mut ether = EtherSimulator::new("my_ether");
+    /// ether.register_driver(WirelessModemFake::new("a"));
+    /// ether.register_driver(WirelessModemFake::new("b"));
+    /// assert_eq!(ether.get_driver_names(), vec!["a", "b"]);
+    /// ```
+    pub fn get_driver_names(&self) -> Vec<String> {
+        let devices = self.devices.lock().expect("Fail to get lock on devices");
+
+        devices
+            .iter()
+            .map(|device| String::from(device.get_name()))
+            .collect()
+    }
+
+    /// Gets count of ticks, during which several devices were broadcasting at once.
+    /// ```
+    /// use proto_lab::{EtherSimulator, IODriverSimulator, WirelessModemFake};
+    ///
+    /// let mut ether = EtherSimulator::new("my_ether");
+    /// let a = WirelessModemFake::new("a");
+    /// let b = WirelessModemFake::new("b");
+    /// ether.register_driver(a.clone());
+    /// ether.register_driver(b.clone());
+    ///
+    /// a.put_to_rx_pin(1);
+    /// b.put_to_rx_pin(2);
+    /// ether.start_tick();
+    /// ether.simulate();
+    /// ether.end_tick();
+    ///
+    /// assert_eq!(ether.get_collision_count(), 1);
+    /// ```
+    pub fn get_collision_count(&self) -> u64 {
+        *self
+            .collision_count
+            .lock()
+            .expect("Fail to get lock on collision count")
+    }
+
     /// Gets the broadcasted byte from latest broadasting device.
     /// That is the place where the data collision is possible.
     fn get_current_byte(&self) -> Option<u8> {
         let mut result: Option<u8> = None;
+        let mut broadcasting_count: u64 = 0;
         let devices = self.devices.lock().expect("Fail to get lock on devices");
 
         for device in devices.iter() {
             if let Some(byte) = device.get_from_device_network_side() {
+                #[cfg(feature = "tracing")]
+                tracing::trace!(parent: &device.span(), ether = %self.name, byte, "byte transmitted");
+
                 result = Some(byte);
+                broadcasting_count += 1;
             }
         }
 
+        if broadcasting_count > 1 {
+            *self
+                .collision_count
+                .lock()
+                .expect("Fail to get lock on collision count") += 1;
+        }
+
         result
     }
 
@@ -131,26 +214,64 @@ impl EtherSimulator {
 
         if let Some(current_byte) = current_byte {
             for device in devices.iter() {
+                #[cfg(feature = "tracing")]
+                tracing::trace!(parent: &device.span(), ether = %self.name, byte = current_byte, "byte delivered");
+
                 device.put_to_device_network_side(current_byte);
             }
         }
     }
+}
+
+impl MediumSimulator for EtherSimulator {
+    fn get_name(&self) -> &str {
+        EtherSimulator::get_name(self)
+    }
+
+    /// Makes the ether pass the tick duration of its simulator to registered devices.
+    fn set_ms_per_tick(&self, ms_per_tick: u64) {
+        *self
+            .ms_per_tick
+            .lock()
+            .expect("Fail to get lock on tick duration") = Some(ms_per_tick);
+        let devices = self.devices.lock().expect("Fail to get lock on devices");
+        for device in devices.iter() {
+            device.set_ms_per_tick(ms_per_tick);
+        }
+    }
+
+    fn start_tick(&self) {
+        EtherSimulator::start_tick(self)
+    }
+
+    fn simulate(&self) {
+        EtherSimulator::simulate(self)
+    }
 
+    fn end_tick(&self) {
+        EtherSimulator::end_tick(self)
+    }
+}
+
+impl Clone for EtherSimulator {
     /// Clones itself.
     /// Also makes all internal data shared to be able to use from multiple threads.
     /// ```
-    /// use network_simulator::EtherSimulator;
-    /// use network_simulator::WirelessModemFake;
-    /// use network_simulator::IODriverSimulator;
+    /// use proto_lab::EtherSimulator;
+    /// use proto_lab::WirelessModemFake;
+    /// use proto_lab::IODriverSimulator;
     ///
     /// let mut ether = EtherSimulator::new("my_ether");
     /// let ether_clone = ether.clone();
     ///
     /// assert_eq!(ether.get_name(), ether_clone.get_name());
-    pub fn clone(&self) -> EtherSimulator {
+    /// ```
+    fn clone(&self) -> EtherSimulator {
         EtherSimulator {
             name: String::from(&self.name),
             devices: Arc::clone(&self.devices),
+            collision_count: Arc::clone(&self.collision_count),
+            ms_per_tick: Arc::clone(&self.ms_per_tick),
         }
     }
 }
@@ -0,0 +1,388 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use crate::{medium::DEFAULT_MS_PER_TICK, EthernetFrame, EthernetNic, MacAddress, MediumSimulator};
+
+/// Default count of frames, which can wait in the egress queue of the port.
+pub const DEFAULT_QUEUE_CAPACITY: usize = 64;
+
+struct Port {
+    nic: EthernetNic,
+    bandwidth_bps: u64,
+    egress_queue: VecDeque<EthernetFrame>,
+    /// Bit time credit of the link from the NIC, in units of bits * 1000.
+    ingress_credit: i64,
+    /// Bit time credit of the link to the NIC, in units of bits * 1000.
+    egress_credit: i64,
+}
+
+struct SwitchState {
+    ports: Vec<Port>,
+    mac_table: HashMap<MacAddress, usize>,
+    queue_capacity: usize,
+    dropped_frame_count: u64,
+    ms_per_tick: u64,
+}
+
+/// Learning switch, which connects `EthernetNic`s with full duplex links.
+/// Each link carries as many frames per tick, as its bandwidth allows.
+/// A frame is sent while the link has bit time credit left, and is paid for afterwards,
+/// so the link may carry up to one frame more than its bandwidth in a tick,
+/// and the overshoot is paid back in the next ticks.
+/// Frames to known unicast addresses are forwarded to the learned port only,
+/// other frames are flooded to all the ports, except the one they came from.
+/// Frames, which do not fit the egress queue of the port, are dropped.
+pub struct EthernetSwitch {
+    name: String,
+    state: Arc<Mutex<SwitchState>>,
+}
+
+impl EthernetSwitch {
+    /// Creates the switch. Link bandwidth uses the tick duration of the simulator,
+    /// the switch is added to.
+    pub fn new(name: &str) -> Self {
+        EthernetSwitch {
+            name: String::from(name),
+            state: Arc::new(Mutex::new(SwitchState {
+                ports: Vec::new(),
+                mac_table: HashMap::new(),
+                queue_capacity: DEFAULT_QUEUE_CAPACITY,
+                dropped_frame_count: 0,
+                ms_per_tick: DEFAULT_MS_PER_TICK,
+            })),
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    fn lock_state(&self) -> std::sync::MutexGuard<'_, SwitchState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|_| panic!("Fail to lock mutex for switch :{}", self.name))
+    }
+
+    /// Connects the NIC to the new port with the link of `bandwidth_bps` bits per second.
+    /// Gives index of the port.
+    /// ```
+    /// use proto_lab::{EthernetNic, EthernetSwitch, MacAddress, NetworkSimulator};
+    ///
+    /// let simulator = NetworkSimulator::new(1);
+    /// let switch = EthernetSwitch::new("switch");
+    /// let a = EthernetNic::new("a", MacAddress([2, 0, 0, 0, 0, 1]));
+    /// let b = EthernetNic::new("b", MacAddress([2, 0, 0, 0, 0, 2]));
+    /// let c = EthernetNic::new("c", MacAddress([2, 0, 0, 0, 0, 3]));
+    /// for nic in [&a, &b, &c] {
+    ///     switch.connect(nic.clone(), 10_000_000);
+    /// }
+    /// simulator.add_medium(switch.clone());
+    ///
+    /// a.send_to(b.get_mac_address(), 0x88B5, b"hello");
+    /// simulator.run_ticks(2);
+    ///
+    /// assert_eq!(b.receive().unwrap().get_payload(), b"hello");
+    /// assert_eq!(c.receive(), None);
+    /// assert_eq!(switch.get_port_of(a.get_mac_address()), Some(0));
+    /// ```
+    pub fn connect(&self, nic: EthernetNic, bandwidth_bps: u64) -> usize {
+        if bandwidth_bps == 0 {
+            panic!(
+                "Bandwidth of the port for nic :{} can not be 0",
+                nic.get_name()
+            );
+        }
+        let mut state = self.lock_state();
+        state.ports.push(Port {
+            nic,
+            bandwidth_bps,
+            egress_queue: VecDeque::new(),
+            ingress_credit: 0,
+            egress_credit: 0,
+        });
+        state.ports.len() - 1
+    }
+
+    /// Disconnects all the NICs with the name. Indexes of the next ports are shifted,
+    /// and the learned addresses are forgotten.
+    pub fn disconnect(&self, name: &str) {
+        let mut state = self.lock_state();
+        state.ports.retain(|port| port.nic.get_name() != name);
+        state.mac_table.clear();
+    }
+
+    /// Gets connected NIC.
+    pub fn get_nic(&self, name: &str) -> Option<EthernetNic> {
+        self.lock_state()
+            .ports
+            .iter()
+            .find(|port| port.nic.get_name() == name)
+            .map(|port| port.nic.clone())
+    }
+
+    /// Gets index of the port, where the address was learned.
+    pub fn get_port_of(&self, mac_address: MacAddress) -> Option<usize> {
+        self.lock_state().mac_table.get(&mac_address).copied()
+    }
+
+    /// Sets count of frames, which can wait in the egress queue of every port.
+    pub fn set_queue_capacity(&self, queue_capacity: usize) {
+        self.lock_state().queue_capacity = queue_capacity;
+    }
+
+    /// Gets count of frames waiting in the egress queue of the port.
+    pub fn get_queue_length(&self, port: usize) -> usize {
+        self.lock_state()
+            .ports
+            .get(port)
+            .map(|port| port.egress_queue.len())
+            .unwrap_or(0)
+    }
+
+    /// Gets count of frames dropped because of full egress queues.
+    pub fn get_dropped_frame_count(&self) -> u64 {
+        self.lock_state().dropped_frame_count
+    }
+
+    fn forward(state: &mut SwitchState, ingress_port: usize, frame: EthernetFrame) {
+        state.mac_table.insert(frame.get_source(), ingress_port);
+
+        let destination = frame.get_destination();
+        let egress_ports: Vec<usize> = match state.mac_table.get(&destination) {
+            Some(port) if !destination.is_multicast() => match *port == ingress_port {
+                true => Vec::new(),
+                false => vec![*port],
+            },
+            _ => (0..state.ports.len())
+                .filter(|port| *port != ingress_port)
+                .collect(),
+        };
+
+        for egress_port in egress_ports {
+            let queue_capacity = state.queue_capacity;
+            let queue = &mut state.ports[egress_port].egress_queue;
+            if queue.len() < queue_capacity {
+                queue.push_back(frame.clone());
+            } else {
+                state.dropped_frame_count += 1;
+            }
+        }
+    }
+}
+
+impl MediumSimulator for EthernetSwitch {
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    fn set_ms_per_tick(&self, ms_per_tick: u64) {
+        self.lock_state().ms_per_tick = ms_per_tick;
+    }
+
+    /// Frames are moved within `simulate`.
+    fn start_tick(&self) {}
+
+    fn simulate(&self) {
+        let mut state = self.lock_state();
+        let ms_per_tick = state.ms_per_tick as i64;
+
+        // Frames received by the switch during this tick are sent out not earlier than next tick.
+        for port in state.ports.iter_mut() {
+            if port.egress_queue.is_empty() {
+                port.egress_credit = port.egress_credit.min(0);
+                continue;
+            }
+            port.egress_credit += port.bandwidth_bps as i64 * ms_per_tick;
+            while let Some(frame) = port.egress_queue.front() {
+                if port.egress_credit <= 0 {
+                    break;
+                }
+                port.egress_credit -= frame.get_length() as i64 * 8 * 1000;
+                let frame = port
+                    .egress_queue
+                    .pop_front()
+                    .expect("Frame shall be queued");
+                port.nic.put_frame(frame);
+            }
+        }
+
+        for ingress_port in 0..state.ports.len() {
+            let mut received_frames = Vec::new();
+            let port = &mut state.ports[ingress_port];
+            if port.nic.peek_frame_length().is_none() {
+                port.ingress_credit = port.ingress_credit.min(0);
+                continue;
+            }
+            port.ingress_credit += port.bandwidth_bps as i64 * ms_per_tick;
+            while port.ingress_credit > 0 {
+                let frame_length = match port.nic.peek_frame_length() {
+                    Some(frame_length) => frame_length,
+                    None => break,
+                };
+                port.ingress_credit -= frame_length as i64 * 8 * 1000;
+                received_frames.extend(port.nic.take_frame());
+            }
+            for frame in received_frames {
+                Self::forward(&mut state, ingress_port, frame);
+            }
+        }
+    }
+
+    /// Frames are moved within `simulate`.
+    fn end_tick(&self) {}
+}
+
+impl Clone for EthernetSwitch {
+    fn clone(&self) -> Self {
+        EthernetSwitch {
+            name: self.name.clone(),
+            state: Arc::clone(&self.state),
+        }
+    }
+}
+
+#[cfg(test)]
+mod ethernet_switch_tests {
+    use crate::{EthernetNic, EthernetSwitch, MacAddress, NetworkSimulator};
+
+    fn create_switch(
+        count: u8,
+        bandwidth_bps: u64,
+    ) -> (NetworkSimulator, EthernetSwitch, Vec<EthernetNic>) {
+        let simulator = NetworkSimulator::new(1);
+        let switch = EthernetSwitch::new("switch");
+        let nics: Vec<EthernetNic> = (0..count)
+            .map(|index| {
+                let nic = EthernetNic::new(
+                    &format!("nic_{}", index),
+                    MacAddress([2, 0, 0, 0, 0, index]),
+                );
+                switch.connect(nic.clone(), bandwidth_bps);
+                nic
+            })
+            .collect();
+        simulator.add_medium(switch.clone());
+        (simulator, switch, nics)
+    }
+
+    #[test]
+    fn test_unknown_unicast_is_flooded_then_learned() {
+        let (simulator, _switch, nics) = create_switch(3, 10_000_000);
+        for nic in nics.iter() {
+            nic.set_promiscuous(true);
+        }
+
+        nics[0].send_to(nics[1].get_mac_address(), 0x88B5, b"first");
+        simulator.run_ticks(2);
+        assert!(nics[1].receive().is_some());
+        assert!(nics[2].receive().is_some());
+
+        nics[1].send_to(nics[0].get_mac_address(), 0x88B5, b"reply");
+        simulator.run_ticks(2);
+        assert!(nics[0].receive().is_some());
+        assert_eq!(nics[2].receive(), None);
+
+        nics[0].send_to(nics[1].get_mac_address(), 0x88B5, b"second");
+        simulator.run_ticks(2);
+        assert!(nics[1].receive().is_some());
+        assert_eq!(nics[2].receive(), None);
+    }
+
+    #[test]
+    fn test_multicast_reaches_joined_nics() {
+        let (simulator, _switch, nics) = create_switch(3, 10_000_000);
+        let group = MacAddress([0x01, 0x00, 0x5E, 0, 0, 1]);
+        nics[2].join_multicast_group(group);
+
+        nics[0].send_to(group, 0x0800, b"group");
+        nics[0].send_to(MacAddress::BROADCAST, 0x0806, b"all");
+        simulator.run_ticks(2);
+
+        assert_eq!(nics[1].receive().unwrap().get_payload(), b"all");
+        assert_eq!(nics[2].receive().unwrap().get_payload(), b"group");
+        assert_eq!(nics[2].receive().unwrap().get_payload(), b"all");
+        assert_eq!(nics[0].receive(), None);
+    }
+
+    #[test]
+    fn test_switch_takes_tick_duration_of_simulator() {
+        // 10 ms of 1 Mbit/s carry 1250 bytes, and frames with 500 bytes of payload are 514 bytes.
+        // After two frames 222 bytes of credit are left, so the third one is sent too,
+        // and the overshoot of 292 bytes leaves 958 bytes for the next tick, so two frames.
+        let simulator = NetworkSimulator::new(10);
+        let switch = EthernetSwitch::new("switch");
+        let a = EthernetNic::new("a", MacAddress([2, 0, 0, 0, 0, 1]));
+        let b = EthernetNic::new("b", MacAddress([2, 0, 0, 0, 0, 2]));
+        switch.connect(a.clone(), 1_000_000);
+        switch.connect(b.clone(), 1_000_000);
+        simulator.add_medium(switch.clone());
+
+        let send_frames = |count| {
+            for _ in 0..count {
+                a.send_to(MacAddress::BROADCAST, 0x88B5, &[0; 500]);
+            }
+        };
+        let count_received = || {
+            let mut received = 0;
+            while b.receive().is_some() {
+                received += 1;
+            }
+            received
+        };
+
+        send_frames(3);
+        simulator.tick();
+        assert_eq!(count_received(), 0);
+        simulator.tick();
+        assert_eq!(count_received(), 3);
+
+        send_frames(3);
+        simulator.run_ticks(2);
+        assert_eq!(count_received(), 2);
+        simulator.tick();
+        assert_eq!(count_received(), 1);
+    }
+
+    #[test]
+    fn test_bandwidth_and_queue_limit() {
+        // 1 Mbit/s carries 125 bytes per millisecond, so every link takes 8 ticks
+        // per frame of 1000 bytes. Both sources get a frame in on ticks 1, 9, 17 and 25,
+        // while the egress port sends one out on ticks 2, 10, 18, 26 and 34.
+        let (simulator, switch, nics) = create_switch(3, 1_000_000);
+        switch.set_queue_capacity(2);
+        for source in [0, 1] {
+            for _ in 0..4 {
+                nics[source].send_to(nics[2].get_mac_address(), 0x88B5, &[0; 986]);
+            }
+        }
+        let count_received = || {
+            let mut received = 0;
+            while nics[2].receive().is_some() {
+                received += 1;
+            }
+            received
+        };
+
+        simulator.run_ticks(2);
+        assert_eq!(count_received(), 1);
+        assert_eq!(switch.get_queue_length(2), 1);
+        assert_eq!(switch.get_dropped_frame_count(), 0);
+
+        // Queue holds one frame, when two more come in, so one of them is dropped.
+        simulator.run_ticks(7);
+        assert_eq!(switch.get_queue_length(2), 2);
+        assert_eq!(switch.get_dropped_frame_count(), 1);
+        assert_eq!(count_received(), 0);
+
+        simulator.run_ticks(1);
+        assert_eq!(count_received(), 1);
+        assert_eq!(switch.get_queue_length(2), 1);
+
+        simulator.run_ticks(100);
+        assert_eq!(count_received(), 3);
+        assert_eq!(switch.get_queue_length(2), 0);
+        assert_eq!(switch.get_dropped_frame_count(), 3);
+    }
+}
@@ -0,0 +1,496 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::{IODriverSimulator, NetworkSimulator, WirelessModemFake};
+
+/// Identifies the partition to be healed.
+/// Create it with `new` to build `Fault::Partition` and `Fault::Heal` for `schedule_fault`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PartitionId(u64);
+
+impl PartitionId {
+    pub fn new(id: u64) -> Self {
+        PartitionId(id)
+    }
+}
+
+/// Fault, which can be applied to the simulator immediately or scheduled for some tick.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fault {
+    /// Splits the devices from the rest of the network.
+    Partition {
+        id: PartitionId,
+        devices: Vec<String>,
+    },
+    /// Restores ether memberships changed by the partition.
+    Heal(PartitionId),
+    /// Powers the device off, see `WirelessModemFake::power_off`.
+    PowerOff { device: String, lose_buffers: bool },
+    /// Powers the device on.
+    PowerOn { device: String },
+    /// Crashes the device, see `WirelessModemFake::crash`.
+    Crash { device: String },
+    /// Crashes the device and powers it on after the delay, see `WirelessModemFake::reboot`.
+    Reboot { device: String, delay_ticks: u64 },
+}
+
+/// Ether membership taken away from the device by the partition.
+struct Membership {
+    ether: String,
+    device: WirelessModemFake,
+    /// Names of the ether members before the first active partition, in order of registration.
+    original_order: Vec<String>,
+}
+
+/// Ether memberships taken away from the devices by the partition.
+struct Partition {
+    shadow_ethers: Vec<String>,
+    memberships: Vec<Membership>,
+}
+
+#[derive(Default)]
+pub(crate) struct FaultPlan {
+    scheduled: BTreeMap<u64, Vec<Fault>>,
+    partitions: HashMap<PartitionId, Partition>,
+    /// Ids of partitions, which are scheduled or applied, to be skipped by `new_partition_id`.
+    used_partition_ids: HashSet<PartitionId>,
+    next_partition_id: u64,
+    /// Member names of the ethers split by active partitions, taken before the first split,
+    /// so the order also covers members taken away by other partitions.
+    /// Entry of the ether is removed once no active partition splits it.
+    original_orders: HashMap<String, Vec<String>>,
+}
+
+impl FaultPlan {
+    fn new_partition_id(&mut self) -> PartitionId {
+        while self
+            .used_partition_ids
+            .contains(&PartitionId(self.next_partition_id))
+        {
+            self.next_partition_id += 1;
+        }
+        let id = PartitionId(self.next_partition_id);
+        self.used_partition_ids.insert(id);
+        id
+    }
+}
+
+/// Finds the position in the ether for the device to be healed, so it gets back
+/// right before the first present member, which was registered after it originally.
+fn original_position(membership: &Membership, members: &[String]) -> usize {
+    let position_of = |name: &str| {
+        membership
+            .original_order
+            .iter()
+            .position(|original| original == name)
+    };
+    let own_position = position_of(membership.device.get_name());
+
+    members
+        .iter()
+        .position(|member| position_of(member) > own_position)
+        .unwrap_or(members.len())
+}
+
+impl NetworkSimulator {
+    /// Splits the devices from the rest of the network.
+    /// In every ether which is shared by the devices and the rest, the devices are moved
+    /// into a separate ether, so they still hear each other, but not the rest.
+    /// ```
+    /// use proto_lab::{IODriverSimulator, NetworkSimulator, Topology};
+    ///
+    /// let simulator = NetworkSimulator::new(1);
+    /// let devices = simulator.generate_topology(&Topology::Line(2), "");
+    ///
+    /// let partition = simulator.partition(&["1"]);
+    /// devices[0].put_to_rx_pin(b'a');
+    /// simulator.tick();
+    /// assert_eq!(devices[1].get_from_tx_pin(), None);
+    ///
+    /// simulator.heal(partition);
+    /// devices[0].put_to_rx_pin(b'b');
+    /// simulator.tick();
+    /// assert_eq!(devices[1].get_from_tx_pin(), Some(b'b'));
+    /// ```
+    pub fn partition(&self, devices: &[&str]) -> PartitionId {
+        let id = self.fault_plan.borrow_mut().new_partition_id();
+        self.apply_fault(Fault::Partition {
+            id,
+            devices: devices.iter().map(|&device| String::from(device)).collect(),
+        });
+        id
+    }
+
+    /// Restores ether memberships changed by the partition.
+    /// Devices get back to their original positions in the ethers, so the same devices
+    /// win collisions as before the partition, whatever order partitions are healed in.
+    pub fn heal(&self, id: PartitionId) {
+        self.apply_fault(Fault::Heal(id));
+    }
+
+    /// Schedules the fault to be applied right before the tick.
+    /// ```
+    /// use proto_lab::{Fault, IODriverSimulator, NetworkSimulator, PartitionId, Topology};
+    ///
+    /// let simulator = NetworkSimulator::new(1);
+    /// let devices = simulator.generate_topology(&Topology::Line(2), "");
+    /// let id = PartitionId::new(7);
+    /// simulator.schedule_fault(1, Fault::Partition { id, devices: vec![String::from("1")] });
+    /// simulator.schedule_fault(2, Fault::Heal(id));
+    ///
+    /// for expected in [Some(b'a'), None, Some(b'c')] {
+    ///     devices[0].put_to_rx_pin(b'a' + simulator.get_current_tick() as u8);
+    ///     simulator.tick();
+    ///     assert_eq!(devices[1].get_from_tx_pin(), expected);
+    /// }
+    /// ```
+    pub fn schedule_fault(&self, tick: u64, fault: Fault) {
+        let mut fault_plan = self.fault_plan.borrow_mut();
+        if let Fault::Partition { id, .. } = fault {
+            fault_plan.used_partition_ids.insert(id);
+        }
+        fault_plan.scheduled.entry(tick).or_default().push(fault);
+    }
+
+    /// Schedules the devices to be split from the rest of the network
+    /// right before the tick `from` and healed right before the tick `to`.
+    /// ```
+    /// use proto_lab::{IODriverSimulator, NetworkSimulator, Topology};
+    ///
+    /// let simulator = NetworkSimulator::new(1);
+    /// let devices = simulator.generate_topology(&Topology::Line(2), "");
+    /// simulator.schedule_partition(&["1"], 1, 2);
+    ///
+    /// for expected in [Some(b'a'), None, Some(b'c')] {
+    ///     devices[0].put_to_rx_pin(b'a' + simulator.get_current_tick() as u8);
+    ///     simulator.tick();
+    ///     assert_eq!(devices[1].get_from_tx_pin(), expected);
+    /// }
+    /// ```
+    pub fn schedule_partition(&self, devices: &[&str], from: u64, to: u64) -> PartitionId {
+        let id = self.fault_plan.borrow_mut().new_partition_id();
+        self.schedule_fault(
+            from,
+            Fault::Partition {
+                id,
+                devices: devices.iter().map(|&device| String::from(device)).collect(),
+            },
+        );
+        self.schedule_fault(to, Fault::Heal(id));
+        id
+    }
+
+    /// Schedules the device to crash right before the tick `at`
+    /// and to be powered on again after `delay_ticks` powered off ticks.
+    /// Reboot countdown is driven by ticks of the ethers, the device is registered in.
+    /// ```
+    /// use proto_lab::{NetworkSimulator, Topology};
+    ///
+    /// let simulator = NetworkSimulator::new(1);
+    /// let devices = simulator.generate_topology(&Topology::Line(2), "");
+    /// simulator.schedule_reboot("1", 1, 2);
+    ///
+    /// simulator.run_ticks(2);
+    /// assert!(!devices[1].is_powered());
+    /// simulator.run_ticks(2);
+    /// assert!(devices[1].is_powered());
+    /// ```
+    pub fn schedule_reboot(&self, device: &str, at: u64, delay_ticks: u64) {
+        self.schedule_fault(
+            at,
+            Fault::Reboot {
+                device: String::from(device),
+                delay_ticks,
+            },
+        );
+    }
+
+    /// Applies the fault immediately.
+    /// Panics if the fault refers to the device, which is unknown to the simulator,
+    /// or if the partition with the same id is already applied and not healed.
+    pub fn apply_fault(&self, fault: Fault) {
+        let find_device = |name: &str| {
+            self.find_driver(name)
+                .unwrap_or_else(|| panic!("Fault refers to unknown device {}", name))
+        };
+
+        match fault {
+            Fault::PowerOff {
+                device,
+                lose_buffers,
+            } => find_device(&device).power_off(lose_buffers),
+            Fault::PowerOn { device } => find_device(&device).power_on(),
+            Fault::Crash { device } => find_device(&device).crash(),
+            Fault::Reboot {
+                device,
+                delay_ticks,
+            } => find_device(&device).reboot(delay_ticks),
+            Fault::Partition { id, devices } => {
+                if self.fault_plan.borrow().partitions.contains_key(&id) {
+                    panic!("Partition {} is already applied", id.0);
+                }
+                self.fault_plan.borrow_mut().used_partition_ids.insert(id);
+
+                let mut partition = Partition {
+                    shadow_ethers: Vec::new(),
+                    memberships: Vec::new(),
+                };
+
+                for mut ether in self.get_ethers() {
+                    let members = ether.get_driver_names();
+                    let split: Vec<&String> = members
+                        .iter()
+                        .filter(|member| devices.contains(member))
+                        .collect();
+
+                    if split.is_empty() || split.len() == members.len() {
+                        continue;
+                    }
+
+                    let shadow_ether_name = format!("{}#partition-{}", ether.get_name(), id.0);
+                    self.create_ether(&shadow_ether_name);
+                    let mut shadow_ether = self
+                        .get_ether(&shadow_ether_name)
+                        .expect("Ether is just created");
+
+                    for name in split {
+                        let device = ether.get_driver(name).expect("Device is a member");
+                        ether.unregister_driver(name);
+                        shadow_ether.register_driver(device.clone());
+                        let mut fault_plan = self.fault_plan.borrow_mut();
+                        let original_order = fault_plan
+                            .original_orders
+                            .entry(String::from(ether.get_name()))
+                            .or_default();
+                        // Devices registered since the first split were registered last.
+                        for member in members.iter() {
+                            if !original_order.contains(member) {
+                                original_order.push(member.clone());
+                            }
+                        }
+                        let original_order = original_order.clone();
+                        drop(fault_plan);
+                        partition.memberships.push(Membership {
+                            ether: String::from(ether.get_name()),
+                            device,
+                            original_order,
+                        });
+                    }
+                    partition.shadow_ethers.push(shadow_ether_name);
+                }
+
+                self.fault_plan
+                    .borrow_mut()
+                    .partitions
+                    .insert(id, partition);
+            }
+            Fault::Heal(id) => {
+                let Some(partition) = self.fault_plan.borrow_mut().partitions.remove(&id) else {
+                    return;
+                };
+
+                for shadow_ether_name in partition.shadow_ethers.iter() {
+                    self.remove_ether(shadow_ether_name);
+                }
+                for membership in partition.memberships.iter() {
+                    if let Some(mut ether) = self.get_ether(&membership.ether) {
+                        let position = original_position(membership, &ether.get_driver_names());
+                        ether.insert_driver(position, membership.device.clone());
+                    }
+                }
+
+                // Orders are taken again once the ethers are split by the next partition.
+                let mut fault_plan = self.fault_plan.borrow_mut();
+                for membership in partition.memberships.iter() {
+                    let is_split = fault_plan.partitions.values().any(|partition| {
+                        partition
+                            .memberships
+                            .iter()
+                            .any(|other| other.ether == membership.ether)
+                    });
+                    if !is_split {
+                        fault_plan.original_orders.remove(&membership.ether);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Applies the faults scheduled for the tick, which is going to be simulated next.
+    pub(crate) fn apply_scheduled_faults(&self) {
+        let tick = self.get_current_tick();
+        let faults = self.fault_plan.borrow_mut().scheduled.remove(&tick);
+
+        for fault in faults.unwrap_or_default() {
+            self.apply_fault(fault);
+        }
+    }
+}
+
+#[cfg(test)]
+mod fault_tests {
+    use crate::{Fault, IODriverSimulator, NetworkSimulator, WirelessModemFake};
+
+    #[test]
+    fn test_partitioned_devices_hear_each_other() {
+        let simulator = NetworkSimulator::new(1);
+        simulator.create_ether("air");
+        let devices: Vec<WirelessModemFake> = ["a", "b", "c"]
+            .iter()
+            .map(|name| WirelessModemFake::new(name))
+            .collect();
+        for device in devices.iter() {
+            simulator
+                .get_ether("air")
+                .unwrap()
+                .register_driver(device.clone());
+        }
+
+        let partition = simulator.partition(&["b", "c"]);
+        devices[1].put_to_rx_pin(b'x');
+        simulator.tick();
+        assert_eq!(devices[0].get_from_tx_pin(), None);
+        assert_eq!(devices[2].get_from_tx_pin(), Some(b'x'));
+
+        simulator.heal(partition);
+        assert_eq!(simulator.get_ethers().len(), 1);
+        assert_eq!(
+            simulator.get_ether("air").unwrap().get_driver_names(),
+            vec!["a", "b", "c"]
+        );
+    }
+
+    #[test]
+    fn test_heal_restores_original_positions_in_any_order() {
+        let simulator = NetworkSimulator::new(1);
+        simulator.create_ether("air");
+        for name in ["a", "b", "c", "d"] {
+            simulator
+                .get_ether("air")
+                .unwrap()
+                .register_driver(WirelessModemFake::new(name));
+        }
+
+        let outer = simulator.partition(&["b", "c"]);
+        let inner = simulator.partition(&["c"]);
+        let other = simulator.partition(&["a"]);
+        simulator.heal(outer);
+        simulator.heal(other);
+        simulator.heal(inner);
+
+        assert_eq!(simulator.get_ethers().len(), 1);
+        assert_eq!(
+            simulator.get_ether("air").unwrap().get_driver_names(),
+            vec!["a", "b", "c", "d"]
+        );
+    }
+
+    #[test]
+    fn test_heal_restores_positions_of_devices_registered_after_earlier_partition() {
+        let simulator = NetworkSimulator::new(1);
+        simulator.create_ether("air");
+        let mut ether = simulator.get_ether("air").unwrap();
+        ether.register_driver(WirelessModemFake::new("a"));
+        ether.register_driver(WirelessModemFake::new("b"));
+
+        let partition = simulator.partition(&["a"]);
+        simulator.heal(partition);
+        ether.register_driver(WirelessModemFake::new("c"));
+        let partition = simulator.partition(&["c"]);
+        simulator.heal(partition);
+
+        assert_eq!(ether.get_driver_names(), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_heal_restores_positions_of_devices_registered_during_partition() {
+        let simulator = NetworkSimulator::new(1);
+        simulator.create_ether("air");
+        let mut ether = simulator.get_ether("air").unwrap();
+        for name in ["a", "b", "c"] {
+            ether.register_driver(WirelessModemFake::new(name));
+        }
+
+        let first = simulator.partition(&["a"]);
+        ether.register_driver(WirelessModemFake::new("d"));
+        let second = simulator.partition(&["d"]);
+        simulator.heal(first);
+        simulator.heal(second);
+
+        assert_eq!(ether.get_driver_names(), vec!["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn test_generated_partition_ids_skip_scheduled_ones() {
+        use crate::PartitionId;
+
+        let simulator = NetworkSimulator::new(1);
+        simulator.schedule_fault(
+            5,
+            Fault::Partition {
+                id: PartitionId::new(0),
+                devices: Vec::new(),
+            },
+        );
+        assert_ne!(simulator.partition(&[]), PartitionId::new(0));
+    }
+
+    #[test]
+    fn test_partition_inside_single_ether_is_noop() {
+        let simulator = NetworkSimulator::new(1);
+        simulator.create_ether("air");
+        simulator
+            .get_ether("air")
+            .unwrap()
+            .register_driver(WirelessModemFake::new("a"));
+
+        simulator.partition(&["a"]);
+        assert_eq!(simulator.get_ethers().len(), 1);
+    }
+
+    #[test]
+    fn test_application_is_rebooted_with_its_device() {
+        use crate::Application;
+        use std::{cell::RefCell, rc::Rc};
+
+        struct Counter(Rc<RefCell<(u32, u32)>>);
+        impl Application for Counter {
+            fn update(&mut self, _modem: &mut WirelessModemFake, _now: u64) {
+                self.0.borrow_mut().0 += 1;
+            }
+            fn reboot(&mut self) {
+                self.0.borrow_mut().1 += 1;
+            }
+        }
+
+        let simulator = NetworkSimulator::new(1);
+        simulator.create_ether("air");
+        let device = WirelessModemFake::new("a");
+        simulator
+            .get_ether("air")
+            .unwrap()
+            .register_driver(device.clone());
+
+        let counts = Rc::new(RefCell::new((0, 0)));
+        simulator.register_application(device, Counter(Rc::clone(&counts)));
+        simulator.schedule_fault(
+            1,
+            Fault::Reboot {
+                device: String::from("a"),
+                delay_ticks: 2,
+            },
+        );
+
+        simulator.run_ticks(5);
+        assert_eq!(*counts.borrow(), (3, 1));
+
+        // Power cycles in between of two ticks are noticed too.
+        simulator.find_driver("a").unwrap().reboot(0);
+        simulator.tick();
+        assert_eq!(*counts.borrow(), (4, 2));
+
+        simulator.find_driver("a").unwrap().crash();
+        simulator.find_driver("a").unwrap().power_on();
+        simulator.tick();
+        assert_eq!(*counts.borrow(), (5, 3));
+    }
+}
@@ -0,0 +1,301 @@
+const SLIP_END: u8 = 0xC0;
+const SLIP_ESC: u8 = 0xDB;
+const SLIP_ESC_END: u8 = 0xDC;
+const SLIP_ESC_ESC: u8 = 0xDD;
+
+const HDLC_FLAG: u8 = 0x7E;
+const HDLC_ESC: u8 = 0x7D;
+const HDLC_XOR: u8 = 0x20;
+
+/// Default maximal length of the decoded frame.
+pub const DEFAULT_MAX_FRAME_LENGTH: usize = 1024;
+
+/// Frame check sequence of HDLC frames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HdlcCrc {
+    /// CRC-16/X.25, as used by PPP.
+    Crc16,
+    /// CRC-32/ISO-HDLC, as used by Ethernet.
+    Crc32,
+}
+
+impl HdlcCrc {
+    fn get_length(&self) -> usize {
+        match self {
+            HdlcCrc::Crc16 => 2,
+            HdlcCrc::Crc32 => 4,
+        }
+    }
+
+    fn calculate(&self, data: &[u8]) -> Vec<u8> {
+        match self {
+            HdlcCrc::Crc16 => {
+                let mut crc: u16 = 0xFFFF;
+                for byte in data {
+                    crc ^= *byte as u16;
+                    for _ in 0..8 {
+                        crc = match crc & 1 {
+                            1 => (crc >> 1) ^ 0x8408,
+                            _ => crc >> 1,
+                        };
+                    }
+                }
+                (!crc).to_le_bytes().to_vec()
+            }
+            HdlcCrc::Crc32 => {
+                let mut crc: u32 = 0xFFFF_FFFF;
+                for byte in data {
+                    crc ^= *byte as u32;
+                    for _ in 0..8 {
+                        crc = match crc & 1 {
+                            1 => (crc >> 1) ^ 0xEDB8_8320,
+                            _ => crc >> 1,
+                        };
+                    }
+                }
+                (!crc).to_le_bytes().to_vec()
+            }
+        }
+    }
+}
+
+/// Way of delimiting frames in the byte stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Framing {
+    /// RFC 1055 byte stuffing with `0xC0` delimiter.
+    Slip,
+    /// Consistent overhead byte stuffing with `0x00` delimiter.
+    Cobs,
+    /// HDLC-like byte stuffing with `0x7E` flags and frame check sequence.
+    Hdlc(HdlcCrc),
+}
+
+impl Framing {
+    /// Encodes the payload into the frame, ready to be sent as the byte stream.
+    /// ```
+    /// use proto_lab::{FrameDecoder, Framing, HdlcCrc};
+    ///
+    /// for framing in [Framing::Slip, Framing::Cobs, Framing::Hdlc(HdlcCrc::Crc16)] {
+    ///     let mut decoder = FrameDecoder::new(framing);
+    ///     let frames: Vec<Vec<u8>> = framing
+    ///         .encode(&[0x00, 0x7E, 0xC0, 0x42])
+    ///         .into_iter()
+    ///         .filter_map(|byte| decoder.push(byte))
+    ///         .collect();
+    ///     assert_eq!(frames, vec![vec![0x00, 0x7E, 0xC0, 0x42]]);
+    /// }
+    /// ```
+    pub fn encode(&self, payload: &[u8]) -> Vec<u8> {
+        match self {
+            Framing::Slip => {
+                let mut frame = vec![SLIP_END];
+                for byte in payload {
+                    match *byte {
+                        SLIP_END => frame.extend([SLIP_ESC, SLIP_ESC_END]),
+                        SLIP_ESC => frame.extend([SLIP_ESC, SLIP_ESC_ESC]),
+                        byte => frame.push(byte),
+                    }
+                }
+                frame.push(SLIP_END);
+                frame
+            }
+            Framing::Cobs => {
+                let mut frame = vec![0];
+                let mut code_index = 0;
+                for byte in payload {
+                    if *byte != 0 {
+                        frame.push(*byte);
+                    }
+                    if *byte == 0 || frame.len() - code_index == 0xFF {
+                        frame[code_index] = (frame.len() - code_index) as u8;
+                        code_index = frame.len();
+                        frame.push(0);
+                    }
+                }
+                frame[code_index] = (frame.len() - code_index) as u8;
+                frame.push(0);
+                frame
+            }
+            Framing::Hdlc(crc) => {
+                let mut frame = vec![HDLC_FLAG];
+                for byte in payload.iter().chain(crc.calculate(payload).iter()) {
+                    match *byte {
+                        HDLC_FLAG | HDLC_ESC => frame.extend([HDLC_ESC, byte ^ HDLC_XOR]),
+                        byte => frame.push(byte),
+                    }
+                }
+                frame.push(HDLC_FLAG);
+                frame
+            }
+        }
+    }
+}
+
+fn decode_cobs(encoded: &[u8]) -> Option<Vec<u8>> {
+    let mut payload = Vec::with_capacity(encoded.len());
+    let mut index = 0;
+    while index < encoded.len() {
+        let code = encoded[index] as usize;
+        if code == 0 || index + code > encoded.len() {
+            return None;
+        }
+        payload.extend_from_slice(&encoded[index + 1..index + code]);
+        index += code;
+        if code < 0xFF && index < encoded.len() {
+            payload.push(0);
+        }
+    }
+    Some(payload)
+}
+
+/// Collects frames from the byte stream. Frames, which are too long, corrupted,
+/// or fail the frame check sequence are dropped and counted.
+pub struct FrameDecoder {
+    framing: Framing,
+    max_frame_length: usize,
+    buffer: Vec<u8>,
+    escaped: bool,
+    overflowed: bool,
+    dropped_frame_count: u64,
+}
+
+impl FrameDecoder {
+    pub fn new(framing: Framing) -> Self {
+        Self::with_max_frame_length(framing, DEFAULT_MAX_FRAME_LENGTH)
+    }
+
+    pub fn with_max_frame_length(framing: Framing, max_frame_length: usize) -> Self {
+        FrameDecoder {
+            framing,
+            max_frame_length,
+            buffer: Vec::new(),
+            escaped: false,
+            overflowed: false,
+            dropped_frame_count: 0,
+        }
+    }
+
+    pub fn get_framing(&self) -> Framing {
+        self.framing
+    }
+
+    /// Gets count of the dropped frames.
+    pub fn get_dropped_frame_count(&self) -> u64 {
+        self.dropped_frame_count
+    }
+
+    /// Feeds the next byte of the stream. Gives the payload once the frame is complete.
+    pub fn push(&mut self, byte: u8) -> Option<Vec<u8>> {
+        let (delimiter, escape) = match self.framing {
+            Framing::Slip => (SLIP_END, Some(SLIP_ESC)),
+            Framing::Cobs => (0x00, None),
+            Framing::Hdlc(_) => (HDLC_FLAG, Some(HDLC_ESC)),
+        };
+
+        if byte == delimiter {
+            return self.finish_frame();
+        }
+        if Some(byte) == escape && !self.escaped {
+            self.escaped = true;
+            return None;
+        }
+
+        let byte = match (self.framing, std::mem::take(&mut self.escaped)) {
+            (Framing::Slip, true) => match byte {
+                SLIP_ESC_END => SLIP_END,
+                SLIP_ESC_ESC => SLIP_ESC,
+                byte => byte,
+            },
+            (Framing::Hdlc(_), true) => byte ^ HDLC_XOR,
+            (_, _) => byte,
+        };
+
+        // Leaves room for the COBS overhead and the frame check sequence.
+        match self.buffer.len() < self.max_frame_length + self.max_frame_length / 254 + 5 {
+            true => self.buffer.push(byte),
+            false => self.overflowed = true,
+        }
+        None
+    }
+
+    fn finish_frame(&mut self) -> Option<Vec<u8>> {
+        let buffer = std::mem::take(&mut self.buffer);
+        let overflowed = std::mem::take(&mut self.overflowed);
+        let escaped = std::mem::take(&mut self.escaped);
+
+        // Empty frames are just the delimiters between the frames.
+        if buffer.is_empty() && !overflowed {
+            return None;
+        }
+
+        let payload = match (overflowed || escaped, self.framing) {
+            (true, _) => None,
+            (false, Framing::Slip) => Some(buffer),
+            (false, Framing::Cobs) => decode_cobs(&buffer),
+            (false, Framing::Hdlc(crc)) => match buffer.len() > crc.get_length() {
+                true => {
+                    let (payload, fcs) = buffer.split_at(buffer.len() - crc.get_length());
+                    match crc.calculate(payload) == fcs {
+                        true => Some(payload.to_vec()),
+                        false => None,
+                    }
+                }
+                false => None,
+            },
+        };
+
+        match payload {
+            Some(payload) if payload.len() <= self.max_frame_length => Some(payload),
+            _ => {
+                self.dropped_frame_count += 1;
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod framing_tests {
+    use super::*;
+
+    fn decode_all(framing: Framing, stream: &[u8]) -> (Vec<Vec<u8>>, u64) {
+        let mut decoder = FrameDecoder::new(framing);
+        let frames = stream
+            .iter()
+            .filter_map(|byte| decoder.push(*byte))
+            .collect();
+        (frames, decoder.get_dropped_frame_count())
+    }
+
+    #[test]
+    fn test_crc_check_values() {
+        assert_eq!(
+            HdlcCrc::Crc16.calculate(b"123456789"),
+            0x906Eu16.to_le_bytes()
+        );
+        assert_eq!(
+            HdlcCrc::Crc32.calculate(b"123456789"),
+            0xCBF4_3926u32.to_le_bytes()
+        );
+    }
+
+    #[test]
+    fn test_cobs_long_runs() {
+        let payload: Vec<u8> = (0..600).map(|index| (index % 255 + 1) as u8).collect();
+        let encoded = Framing::Cobs.encode(&payload);
+        assert!(!encoded[..encoded.len() - 1].contains(&0));
+        assert_eq!(decode_all(Framing::Cobs, &encoded).0, vec![payload]);
+        assert_eq!(Framing::Cobs.encode(&[0, 0]), vec![1, 1, 1, 0]);
+    }
+
+    #[test]
+    fn test_corrupted_hdlc_frame_is_dropped() {
+        let mut stream = Framing::Hdlc(HdlcCrc::Crc32).encode(b"first");
+        stream[3] ^= 0x01;
+        stream.extend(Framing::Hdlc(HdlcCrc::Crc32).encode(b"second"));
+
+        let (frames, dropped) = decode_all(Framing::Hdlc(HdlcCrc::Crc32), &stream);
+        assert_eq!(frames, vec![b"second".to_vec()]);
+        assert_eq!(dropped, 1);
+    }
+}
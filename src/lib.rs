@@ -1,7 +1,59 @@
+mod application;
+mod arq;
+mod can_bus;
+#[cfg(feature = "config")]
+mod config;
+mod delivery_probe;
 mod device;
 mod ether_simulator;
+mod ethernet_switch;
+mod fault;
+mod framing;
+mod mac;
+mod medium;
 mod network_simulator;
+mod rng;
+mod rs485_bus;
+mod scenario;
+mod serial_link;
+#[cfg(feature = "smoltcp")]
+mod smoltcp_device;
+#[cfg(all(feature = "tap", target_os = "linux"))]
+mod tap_bridge;
+mod topology;
+mod topology_export;
+mod traffic;
+mod uart;
 
-pub use device::{IODriverSimulator, /*WiredModemFake*/ WirelessModemFake};
+pub use application::Application;
+pub use arq::{ArqMode, ArqReceiver, ArqSender, ArqStats};
+pub use can_bus::CanBus;
+#[cfg(feature = "config")]
+pub use config::{ConfigError, ConfigFormat, DeviceConfig, EtherConfig, NetworkConfig};
+pub use delivery_probe::{DeliveryProbe, DeliveryReport, MessageRecord, TAGGED_MESSAGE_LENGTH};
+pub use device::{
+    AddressedModem, AtCommandModem, AtRequest, AtResponse, AtSettings, CanController,
+    CanErrorState, CanFrame, EnergyModel, EnergyReport, EthernetFrame, EthernetNic, FramedModem,
+    IODriverSimulator, MacAddress, ModePinFake, RadioMode, Rs485TransceiverFake, SerialError,
+    SerialPortFake, StatusPin, StatusPinFake, TransmissionMode,
+    /*WiredModemFake*/ WirelessModemFake, BROADCAST_ADDRESS, CAN_MAX_DATA_LENGTH,
+    CAN_MAX_EXTENDED_ID, CAN_MAX_STANDARD_ID, ETHERNET_HEADER_LENGTH, ETHERNET_MTU,
+};
 pub use ether_simulator::EtherSimulator;
+pub use ethernet_switch::{EthernetSwitch, DEFAULT_QUEUE_CAPACITY};
+pub use fault::{Fault, PartitionId};
+pub use framing::{FrameDecoder, Framing, HdlcCrc, DEFAULT_MAX_FRAME_LENGTH};
+pub use mac::{MacNode, MacProtocol, MacStats, DEFAULT_MAC_QUEUE_LENGTH};
+pub use medium::MediumSimulator;
 pub use network_simulator::NetworkSimulator;
+pub use rs485_bus::Rs485Bus;
+pub use scenario::{ExpectationResult, Scenario, ScenarioAction, ScenarioReport};
+pub use serial_link::SerialLink;
+#[cfg(feature = "smoltcp")]
+pub use smoltcp_device::{SmoltcpDevice, SmoltcpRxToken, SmoltcpTxToken, DEFAULT_SMOLTCP_MTU};
+#[cfg(all(feature = "tap", target_os = "linux"))]
+pub use tap_bridge::{TapBridge, TapNamespaceError};
+pub use topology::Topology;
+pub use topology_export::Adjacency;
+pub use traffic::{SinkStats, SourceStats, TrafficPattern, TrafficSink, TrafficSource};
+pub use uart::{LineConfig, Parity, StopBits};
@@ -0,0 +1,427 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    rng::Rng, Application, FrameDecoder, Framing, HdlcCrc, IODriverSimulator, WirelessModemFake,
+};
+
+/// Framing of MAC frames. Frame check sequence lets collided frames be dropped.
+const MAC_FRAMING: Framing = Framing::Hdlc(HdlcCrc::Crc16);
+
+/// Length of the MAC header: node id of the sender and sequence number of the frame.
+const MAC_HEADER_LENGTH: usize = 4;
+
+/// Default count of frames, which can wait for transmission.
+pub const DEFAULT_MAC_QUEUE_LENGTH: usize = 16;
+
+/// Medium access strategy of `MacNode`. None of them uses acknowledgements,
+/// so every frame is transmitted once, and collided frames are lost.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MacProtocol {
+    /// Transmits as soon as the frame is queued and the own previous frame is sent.
+    Aloha,
+    /// Starts transmissions only at the slot boundaries of virtual time.
+    /// Slot of one tick makes the simulator tick the slot.
+    SlottedAloha { slot_ms: u64 },
+    /// Listens before talking: waits for the channel to be idle for `difs_ticks`,
+    /// then counts down random backoff of up to `contention_window` idle ticks.
+    /// The countdown is frozen while the channel is busy.
+    CsmaCa {
+        difs_ticks: u64,
+        contention_window: u64,
+    },
+}
+
+/// Figures of the `MacNode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MacStats {
+    /// Count of frames queued by `send` or by generated traffic.
+    pub offered_frames: u64,
+    /// Count of frames dropped because of full queue.
+    pub dropped_frames: u64,
+    pub transmitted_frames: u64,
+    /// Count of frames received intact from other nodes.
+    pub received_frames: u64,
+    /// Count of payload bytes of the received frames.
+    pub received_bytes: u64,
+    /// Count of received frames, which were dropped as corrupted by collisions.
+    pub corrupted_frames: u64,
+    /// Count of frames of other nodes lost on the air, found by gaps in their sequence numbers.
+    /// Unlike `corrupted_frames`, it includes frames fully overwritten by a colliding frame.
+    /// Frames lost after the last frame received from the node are not counted.
+    pub collided_frames: u64,
+    /// Count of ticks CSMA/CA waited because of the busy channel.
+    pub deferrals: u64,
+}
+
+struct Traffic {
+    arrival_probability: f64,
+    payload_length: usize,
+    rng: Rng,
+}
+
+/// Frames received from one sender.
+struct SenderRecord {
+    received_frames: u64,
+    next_sequence: u16,
+}
+
+struct MacState {
+    protocol: MacProtocol,
+    node_id: u16,
+    next_sequence: u16,
+    queue: VecDeque<Vec<u8>>,
+    max_queue_length: usize,
+    decoder: FrameDecoder,
+    traffic: Option<Traffic>,
+    rng: Rng,
+    idle_ticks: u64,
+    backoff: Option<u64>,
+    stats: MacStats,
+    senders: BTreeMap<u16, SenderRecord>,
+}
+
+/// Node running the reference MAC protocol as `Application` on its modem.
+/// It transmits queued frames according to the protocol, and receives frames of other nodes,
+/// so it can be used as the traffic source, the sink, or both.
+/// Every frame carries node id of its sender and the sequence number,
+/// so received frames are counted per sender.
+///
+/// Register a clone of it, and keep the other one to send frames and read the figures.
+/// ```
+/// use proto_lab::{MacNode, MacProtocol, NetworkSimulator, WirelessModemFake};
+///
+/// let simulator = NetworkSimulator::new(1);
+/// simulator.create_ether("air");
+/// let mut ether = simulator.get_ether("air").unwrap();
+/// let protocol = MacProtocol::CsmaCa { difs_ticks: 2, contention_window: 16 };
+///
+/// let sink = MacNode::new(protocol, 0, 0);
+/// let sink_modem = WirelessModemFake::new("sink");
+/// ether.register_driver(sink_modem.clone());
+/// simulator.register_application(sink_modem, sink.clone());
+///
+/// for node_id in 1..=3 {
+///     let node = MacNode::new(protocol, node_id, node_id as u64);
+///     node.send(b"hello");
+///     let modem = WirelessModemFake::new(&format!("node_{}", node_id));
+///     ether.register_driver(modem.clone());
+///     simulator.register_application(modem, node);
+/// }
+/// simulator.run_ticks(200);
+///
+/// assert_eq!(sink.get_stats().received_frames, 3);
+/// assert_eq!(sink.get_received_frames_by_sender().get(&2), Some(&1));
+/// ```
+pub struct MacNode {
+    state: Arc<Mutex<MacState>>,
+}
+
+impl MacNode {
+    /// Creates the node. `node_id` identifies its frames to the receivers.
+    /// `seed` makes its random choices reproducible, so give different seeds to different nodes.
+    pub fn new(protocol: MacProtocol, node_id: u16, seed: u64) -> Self {
+        if let MacProtocol::SlottedAloha { slot_ms: 0 } = protocol {
+            panic!("Slot of slotted ALOHA can not be 0 ms");
+        }
+        MacNode {
+            state: Arc::new(Mutex::new(MacState {
+                protocol,
+                node_id,
+                next_sequence: 0,
+                queue: VecDeque::new(),
+                max_queue_length: DEFAULT_MAC_QUEUE_LENGTH,
+                decoder: FrameDecoder::new(MAC_FRAMING),
+                traffic: None,
+                rng: Rng::new(seed),
+                idle_ticks: 0,
+                backoff: None,
+                stats: MacStats::default(),
+                senders: BTreeMap::new(),
+            })),
+        }
+    }
+
+    fn lock_state(&self) -> std::sync::MutexGuard<'_, MacState> {
+        self.state.lock().expect("Fail to lock MAC node state")
+    }
+
+    /// Queues the frame for transmission. Drops it if the queue is full.
+    pub fn send(&self, payload: &[u8]) {
+        self.lock_state().offer(payload.to_vec());
+    }
+
+    /// Generates frames of `payload_length` bytes, queuing a new one every tick
+    /// with `arrival_probability`.
+    pub fn set_traffic(&self, arrival_probability: f64, payload_length: usize, seed: u64) {
+        self.lock_state().traffic = Some(Traffic {
+            arrival_probability,
+            payload_length,
+            rng: Rng::new(seed),
+        });
+    }
+
+    pub fn set_max_queue_length(&self, max_queue_length: usize) {
+        self.lock_state().max_queue_length = max_queue_length;
+    }
+
+    pub fn get_queue_length(&self) -> usize {
+        self.lock_state().queue.len()
+    }
+
+    pub fn get_stats(&self) -> MacStats {
+        self.lock_state().stats
+    }
+
+    /// Gets count of frames received intact from every other node by its node id.
+    pub fn get_received_frames_by_sender(&self) -> BTreeMap<u16, u64> {
+        self.lock_state()
+            .senders
+            .iter()
+            .map(|(node_id, record)| (*node_id, record.received_frames))
+            .collect()
+    }
+}
+
+impl MacState {
+    fn offer(&mut self, payload: Vec<u8>) {
+        self.stats.offered_frames += 1;
+        match self.queue.len() < self.max_queue_length {
+            true => self.queue.push_back(payload),
+            false => self.stats.dropped_frames += 1,
+        }
+    }
+
+    fn receive(&mut self, frame: Vec<u8>) {
+        if frame.len() < MAC_HEADER_LENGTH {
+            self.stats.corrupted_frames += 1;
+            return;
+        }
+        let node_id = u16::from_be_bytes([frame[0], frame[1]]);
+        let sequence = u16::from_be_bytes([frame[2], frame[3]]);
+
+        let record = self.senders.entry(node_id).or_insert(SenderRecord {
+            received_frames: 0,
+            next_sequence: 0,
+        });
+        let gap = sequence.wrapping_sub(record.next_sequence);
+        // Sequence going back means the sender was rebooted.
+        if gap < 0x8000 {
+            self.stats.collided_frames += gap as u64;
+        }
+        record.next_sequence = sequence.wrapping_add(1);
+        record.received_frames += 1;
+
+        self.stats.received_frames += 1;
+        self.stats.received_bytes += (frame.len() - MAC_HEADER_LENGTH) as u64;
+    }
+
+    /// Prepends the MAC header to the payload.
+    fn encode(&mut self, payload: &[u8]) -> Vec<u8> {
+        let mut frame = Vec::with_capacity(MAC_HEADER_LENGTH + payload.len());
+        frame.extend(self.node_id.to_be_bytes());
+        frame.extend(self.next_sequence.to_be_bytes());
+        frame.extend(payload);
+        self.next_sequence = self.next_sequence.wrapping_add(1);
+        MAC_FRAMING.encode(&frame)
+    }
+
+    /// Tells if the queued frame can be transmitted during this tick.
+    fn may_transmit(&mut self, channel_idle: bool, now: u64) -> bool {
+        match self.protocol {
+            MacProtocol::Aloha => true,
+            MacProtocol::SlottedAloha { slot_ms } => now.is_multiple_of(slot_ms),
+            MacProtocol::CsmaCa {
+                difs_ticks,
+                contention_window,
+            } => {
+                if !channel_idle {
+                    self.stats.deferrals += 1;
+                    return false;
+                }
+                if self.idle_ticks < difs_ticks {
+                    return false;
+                }
+                let backoff = match self.backoff {
+                    Some(backoff) => backoff,
+                    None => self.rng.next_u64() % contention_window.max(1),
+                };
+                match backoff {
+                    0 => {
+                        self.backoff = None;
+                        true
+                    }
+                    backoff => {
+                        self.backoff = Some(backoff - 1);
+                        false
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Clone for MacNode {
+    fn clone(&self) -> Self {
+        MacNode {
+            state: Arc::clone(&self.state),
+        }
+    }
+}
+
+impl Application for MacNode {
+    fn update(&mut self, modem: &mut WirelessModemFake, now: u64) {
+        let mut state = self.lock_state();
+
+        while let Some(byte) = modem.get_from_tx_pin() {
+            let dropped_frame_count = state.decoder.get_dropped_frame_count();
+            let frame = state.decoder.push(byte);
+            state.stats.corrupted_frames +=
+                state.decoder.get_dropped_frame_count() - dropped_frame_count;
+            if let Some(frame) = frame {
+                state.receive(frame);
+            }
+        }
+
+        if let Some(traffic) = state.traffic.as_mut() {
+            if traffic.rng.next_f64() < traffic.arrival_probability {
+                let payload = vec![0xA5; traffic.payload_length];
+                state.offer(payload);
+            }
+        }
+
+        // Own frame is still being transmitted.
+        if modem.is_busy() {
+            state.idle_ticks = 0;
+            return;
+        }
+
+        let channel_idle = !modem.is_receiving();
+        match channel_idle {
+            true => state.idle_ticks += 1,
+            false => state.idle_ticks = 0,
+        }
+
+        if state.queue.is_empty() || !state.may_transmit(channel_idle, now) {
+            return;
+        }
+
+        let payload = state.queue.pop_front().expect("Queue shall not be empty");
+        for byte in state.encode(&payload) {
+            modem.put_to_rx_pin(byte);
+        }
+        state.stats.transmitted_frames += 1;
+        state.idle_ticks = 0;
+    }
+
+    /// Queued frames are lost with the state of the device, and sequence numbers start over.
+    fn reboot(&mut self) {
+        let mut state = self.lock_state();
+        state.queue.clear();
+        state.next_sequence = 0;
+        state.backoff = None;
+        state.idle_ticks = 0;
+        state.decoder = FrameDecoder::new(MAC_FRAMING);
+    }
+}
+
+#[cfg(test)]
+mod mac_tests {
+    use crate::{MacNode, MacProtocol, MacStats, NetworkSimulator, WirelessModemFake};
+
+    /// Runs saturated senders and gives figures of the sink,
+    /// together with count of frames transmitted by the senders.
+    fn run_contention(protocol: MacProtocol) -> (MacStats, u64) {
+        let simulator = NetworkSimulator::new(1);
+        simulator.create_ether("air");
+        let mut ether = simulator.get_ether("air").unwrap();
+
+        let sink = MacNode::new(protocol, 0, 0);
+        let sink_modem = WirelessModemFake::new("sink");
+        ether.register_driver(sink_modem.clone());
+        simulator.register_application(sink_modem, sink.clone());
+
+        let mut nodes = Vec::new();
+        for node_id in 1..=4 {
+            let node = MacNode::new(protocol, node_id, node_id as u64);
+            node.set_traffic(0.02, 10, node_id as u64 + 100);
+            let modem = WirelessModemFake::new(&format!("node_{}", node_id));
+            ether.register_driver(modem.clone());
+            simulator.register_application(modem, node.clone());
+            nodes.push(node);
+        }
+
+        simulator.run_ticks(5000);
+
+        let received_by_sender = sink.get_received_frames_by_sender();
+        assert_eq!(
+            received_by_sender.keys().copied().collect::<Vec<_>>(),
+            vec![1, 2, 3, 4]
+        );
+        assert_eq!(
+            received_by_sender.values().sum::<u64>(),
+            sink.get_stats().received_frames
+        );
+        let transmitted = nodes
+            .iter()
+            .map(|node| node.get_stats().transmitted_frames)
+            .sum();
+        (sink.get_stats(), transmitted)
+    }
+
+    #[test]
+    fn test_csma_ca_outperforms_aloha() {
+        let (aloha, aloha_transmitted) = run_contention(MacProtocol::Aloha);
+        let (slotted, _) = run_contention(MacProtocol::SlottedAloha { slot_ms: 20 });
+        let (csma, csma_transmitted) = run_contention(MacProtocol::CsmaCa {
+            difs_ticks: 2,
+            contention_window: 16,
+        });
+
+        assert!(aloha.received_frames + aloha.collided_frames <= aloha_transmitted);
+        assert!(csma.received_frames + csma.collided_frames <= csma_transmitted);
+        assert!(aloha.corrupted_frames > 0);
+        assert!(aloha.collided_frames >= aloha.corrupted_frames);
+        assert!(aloha.collided_frames > 2 * csma.collided_frames);
+        assert!(slotted.received_frames > aloha.received_frames);
+        assert!(csma.received_frames > slotted.received_frames);
+    }
+
+    #[test]
+    fn test_overwritten_frame_is_counted_as_collided() {
+        let simulator = NetworkSimulator::new(1);
+        simulator.create_ether("air");
+        let mut ether = simulator.get_ether("air").unwrap();
+
+        let sink = MacNode::new(MacProtocol::Aloha, 0, 0);
+        let sink_modem = WirelessModemFake::new("sink");
+        ether.register_driver(sink_modem.clone());
+        simulator.register_application(sink_modem, sink.clone());
+
+        // First frames of both nodes are sent at once and differ only in the header,
+        // so the frame of the last registered node overwrites the other one.
+        for node_id in 1..=2 {
+            let node = MacNode::new(MacProtocol::Aloha, node_id, node_id as u64);
+            for _ in node_id..=2 {
+                node.send(&[0xA5; 10]);
+            }
+            let modem = WirelessModemFake::new(&format!("node_{}", node_id));
+            ether.register_driver(modem.clone());
+            simulator.register_application(modem, node);
+        }
+        simulator.run_ticks(100);
+
+        let stats = sink.get_stats();
+        assert_eq!(stats.corrupted_frames, 0);
+        assert_eq!(stats.received_frames, 2);
+        assert_eq!(stats.collided_frames, 1);
+        assert_eq!(
+            sink.get_received_frames_by_sender()
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec![(1, 1), (2, 1)]
+        );
+    }
+}
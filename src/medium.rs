@@ -0,0 +1,23 @@
+/// Tick duration of media and devices, which are not added to a simulator.
+pub(crate) const DEFAULT_MS_PER_TICK: u64 = 1;
+
+/// Transmission medium, which can be simulated by `NetworkSimulator` next to the ethers,
+/// like a wired bus or a point-to-point link.
+/// Simulation of a tick is done in three steps, the same as for `EtherSimulator`:
+/// every medium gets `start_tick`, then `simulate`, then `end_tick`.
+pub trait MediumSimulator {
+    fn get_name(&self) -> &str;
+
+    /// Is called by `NetworkSimulator::add_medium` with the tick duration of the simulator,
+    /// for media, which move data at some rate.
+    fn set_ms_per_tick(&self, _ms_per_tick: u64) {}
+
+    /// Prepares all the connected devices for starting of simulation during tick.
+    fn start_tick(&self);
+
+    /// Moves the data between the connected devices. Called only during tick is active.
+    fn simulate(&self);
+
+    /// Prepares all the connected devices for ending of simulation during tick.
+    fn end_tick(&self);
+}
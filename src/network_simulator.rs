@@ -4,22 +4,46 @@ use std::{
     sync::{Arc, Mutex},
 };
 
-use crate::EtherSimulator;
+use crate::{
+    fault::FaultPlan, Application, DeliveryProbe, EnergyReport, EtherSimulator, IODriverSimulator,
+    MediumSimulator, WirelessModemFake,
+};
+
+type Media = Vec<Box<dyn MediumSimulator + Send>>;
 
 pub struct NetworkSimulator {
     ethers: RefCell<Option<Vec<EtherSimulator>>>,
+    media: RefCell<Option<Media>>,
     ms_per_tick: u64,
-    simulation_thread_handle: Option<std::thread::JoinHandle<Vec<EtherSimulator>>>,
+    tick_count: Arc<Mutex<u64>>,
+    applications: RefCell<Vec<RegisteredApplication>>,
+    probes: Arc<Mutex<Vec<DeliveryProbe>>>,
+    pub(crate) fault_plan: RefCell<FaultPlan>,
+    simulation_thread_handle: Option<std::thread::JoinHandle<(Vec<EtherSimulator>, Media)>>,
     thread_killer: Arc<Mutex<bool>>,
 }
 
+struct RegisteredApplication {
+    modem: WirelessModemFake,
+    application: Box<dyn Application>,
+    /// Power cycle count of the modem, when the application was last updated.
+    /// `None` if the modem was not powered at registration.
+    seen_power_cycle_count: Option<u64>,
+}
+
 /// NetworkSimulator is designed to simulate the network which consist of 1+ ethers.
-/// Each ether is instance of EtherSimulator
+/// Each ether is instance of EtherSimulator.
+/// Other media, like wired buses, are simulated next to the ethers once added with `add_medium`.
 impl NetworkSimulator {
     pub fn new(ms_per_tick: u64) -> Self {
         NetworkSimulator {
             ethers: RefCell::new(Some(Vec::new())),
+            media: RefCell::new(Some(Vec::new())),
             ms_per_tick,
+            tick_count: Arc::new(Mutex::new(0)),
+            applications: RefCell::new(Vec::new()),
+            probes: Arc::new(Mutex::new(Vec::new())),
+            fault_plan: RefCell::new(FaultPlan::default()),
             simulation_thread_handle: None,
             thread_killer: Arc::new(Mutex::new(false)),
         }
@@ -29,6 +53,7 @@ impl NetworkSimulator {
         match self.ethers.borrow_mut().deref_mut() {
             Some(ref mut ethers) => {
                 let new_ether = EtherSimulator::new(name);
+                new_ether.set_ms_per_tick(self.ms_per_tick);
                 ethers.push(new_ether);
             }
             None => {
@@ -37,6 +62,41 @@ impl NetworkSimulator {
         };
     }
 
+    /// Adds the medium to be simulated next to the ethers.
+    /// Medium is moved into the simulator, so keep a clone of it to access its devices.
+    /// Medium gets the tick duration of the simulator with `set_ms_per_tick`.
+    pub fn add_medium(&self, medium: impl MediumSimulator + Send + 'static) {
+        medium.set_ms_per_tick(self.ms_per_tick);
+        match self.media.borrow_mut().deref_mut() {
+            Some(ref mut media) => media.push(Box::new(medium)),
+            None => {
+                panic!("Simulation thread is already started. Can not change configuration")
+            }
+        };
+    }
+
+    /// Attaches the delivery probe, which then scans its destinations after every tick,
+    /// and stamps the messages with ticks of this simulator.
+    /// Probe is moved into the simulator, so keep a clone of it to read results.
+    pub fn attach_probe(&self, probe: DeliveryProbe) {
+        if self.simulation_thread_handle.is_some() {
+            panic!("Simulation thread is already started. Can not change configuration")
+        }
+        probe.attach(Arc::clone(&self.tick_count));
+        self.probes
+            .lock()
+            .expect("Fail to get lock on probes")
+            .push(probe);
+    }
+
+    /// Removes the medium added by `add_medium`.
+    pub fn remove_medium(&self, name: &str) {
+        match self.media.borrow_mut().deref_mut() {
+            None => panic!("Simulation thread is started. Can not remove medium"),
+            Some(ref mut media) => media.retain(|medium| medium.get_name() != name),
+        }
+    }
+
     pub fn get_ether(&self, name: &str) -> Option<EtherSimulator> {
         match self.ethers.borrow_mut().deref_mut() {
             None => panic!("Simulation thread is started. Can not get ether"),
@@ -51,6 +111,96 @@ impl NetworkSimulator {
         }
     }
 
+    /// Removes the ether. Devices registered in it stay registered in other ethers.
+    pub fn remove_ether(&self, name: &str) {
+        match self.ethers.borrow_mut().deref_mut() {
+            None => panic!("Simulation thread is started. Can not remove ether"),
+            Some(ref mut ethers) => ethers.retain(|ether| ether.get_name() != name),
+        }
+    }
+
+    /// Finds the device by name among devices registered in ethers or with applications.
+    pub fn find_driver(&self, name: &str) -> Option<WirelessModemFake> {
+        for ether in self.get_ethers() {
+            if let Some(device) = ether.get_driver(name) {
+                return Some(device);
+            }
+        }
+        self.applications
+            .borrow()
+            .iter()
+            .find(|registered| registered.modem.get_name() == name)
+            .map(|registered| registered.modem.clone())
+    }
+
+    /// Gets all the devices registered in ethers or with applications, each device once.
+    pub fn get_drivers(&self) -> Vec<WirelessModemFake> {
+        let mut devices: Vec<WirelessModemFake> = Vec::new();
+        let ether_devices = self.get_ethers().into_iter().flat_map(|ether| {
+            ether
+                .get_driver_names()
+                .into_iter()
+                .filter_map(move |name| ether.get_driver(&name))
+        });
+        let application_devices: Vec<WirelessModemFake> = self
+            .applications
+            .borrow()
+            .iter()
+            .map(|registered| registered.modem.clone())
+            .collect();
+
+        for device in ether_devices.chain(application_devices) {
+            if !devices
+                .iter()
+                .any(|known| known.get_name() == device.get_name())
+            {
+                devices.push(device);
+            }
+        }
+        devices
+    }
+
+    /// Gets energy consumed by every device with the energy model set.
+    /// ```
+    /// use proto_lab::{EnergyModel, NetworkSimulator, Topology};
+    ///
+    /// let simulator = NetworkSimulator::new(10);
+    /// let devices = simulator.generate_topology(&Topology::Line(2), "");
+    /// devices[0].set_energy_model(EnergyModel {
+    ///     voltage_v: 3.3,
+    ///     tx_current_ma: 120.0,
+    ///     rx_current_ma: 12.0,
+    ///     idle_current_ma: 12.0,
+    ///     sleep_current_ma: 0.001,
+    ///     battery_capacity_mah: Some(2400.0),
+    /// });
+    ///
+    /// simulator.run_ticks(10);
+    ///
+    /// let reports = simulator.get_energy_reports();
+    /// assert_eq!(reports.len(), 1);
+    /// assert_eq!(reports[0].0, "0");
+    /// assert_eq!(reports[0].1.idle_ticks, 10);
+    /// ```
+    pub fn get_energy_reports(&self) -> Vec<(String, EnergyReport)> {
+        self.get_drivers()
+            .into_iter()
+            .filter_map(|device| {
+                device
+                    .get_energy_report()
+                    .map(|report| (String::from(device.get_name()), report))
+            })
+            .collect()
+    }
+
+    /// Gets all the ethers in order of creation.
+    pub fn get_ethers(&self) -> Vec<EtherSimulator> {
+        match self.ethers.borrow_mut().deref_mut() {
+            None => panic!("Simulation thread is started. Can not get ethers"),
+            Some(ref ethers) => ethers.to_vec(),
+        }
+    }
+
     pub fn start_tick(&self) {
         match self.ethers.borrow_mut().deref_mut() {
             None => panic!(
@@ -62,6 +212,16 @@ impl NetworkSimulator {
                 }
             }
         }
+        match self.media.borrow_mut().deref_mut() {
+            None => panic!(
+                "Simulation thread is started. Can not do start_tick and thread at the same time"
+            ),
+            Some(ref media) => {
+                for medium in media.iter() {
+                    medium.start_tick();
+                }
+            }
+        }
     }
 
     pub fn end_tick(&self) {
@@ -75,6 +235,32 @@ impl NetworkSimulator {
                 }
             }
         }
+        match self.media.borrow_mut().deref_mut() {
+            None => panic!(
+                "Simulation thread is started. Can not do end_tick and thread at the same time"
+            ),
+            Some(ref media) => {
+                for medium in media.iter() {
+                    medium.end_tick();
+                }
+            }
+        }
+        let tick = {
+            let mut tick_count = self
+                .tick_count
+                .lock()
+                .expect("Fail to get lock on tick count");
+            *tick_count += 1;
+            *tick_count
+        };
+        for probe in self
+            .probes
+            .lock()
+            .expect("Fail to get lock on probes")
+            .iter()
+        {
+            probe.scan(tick);
+        }
     }
 
     pub fn simulate(&self) {
@@ -88,16 +274,169 @@ impl NetworkSimulator {
                 }
             }
         }
+        match self.media.borrow_mut().deref_mut() {
+            None => panic!(
+                "Simulation thread is started. Can not do simulate and thread at the same time"
+            ),
+            Some(ref media) => {
+                for medium in media.iter() {
+                    medium.simulate();
+                }
+            }
+        }
+    }
+
+    /// Registers the application, which will be updated with the modem once per tick by `tick`.
+    /// Applications are updated in order of registration.
+    /// The modem gets the tick duration of the simulator for its energy accounting.
+    /// ```
+    /// use proto_lab::{NetworkSimulator, WirelessModemFake};
+    ///
+    /// let simulator = NetworkSimulator::new(1);
+    /// simulator.create_ether("1");
+    ///
+    /// let sender = WirelessModemFake::new("sender");
+    /// let receiver = WirelessModemFake::new("receiver");
+    /// simulator.get_ether("1").unwrap().register_driver(sender.clone());
+    /// simulator.get_ether("1").unwrap().register_driver(receiver.clone());
+    ///
+    /// simulator.register_application(sender, |modem: &mut WirelessModemFake, now: u64| {
+    ///     if now == 0 {
+    ///         modem.write(b"hi").unwrap();
+    ///     }
+    /// });
+    ///
+    /// simulator.run_ticks(2);
+    ///
+    /// let mut buf = [0u8; 2];
+    /// assert_eq!(receiver.read(&mut buf).unwrap(), 2);
+    /// assert_eq!(&buf, b"hi");
+    /// ```
+    pub fn register_application(
+        &self,
+        modem: WirelessModemFake,
+        application: impl Application + 'static,
+    ) {
+        if self.simulation_thread_handle.is_some() {
+            panic!("Simulation thread is already started. Can not change configuration")
+        }
+        modem.set_ms_per_tick(self.ms_per_tick);
+        self.applications.borrow_mut().push(RegisteredApplication {
+            seen_power_cycle_count: modem.is_powered().then(|| modem.get_power_cycle_count()),
+            modem,
+            application: Box::new(application),
+        });
+    }
+
+    /// Runs one whole tick: applies the faults scheduled for the tick,
+    /// updates the registered applications, then does `start_tick`, `simulate` and `end_tick`.
+    /// Applications of powered off modems are not updated, and get `reboot` called
+    /// once their modem is powered on again, also if the power was cycled in between of two ticks.
+    /// With the `tracing` feature enabled, the tick runs inside of `tick_span`,
+    /// and every application is updated inside of the span of its modem.
+    /// ```
+    /// use proto_lab::{NetworkSimulator, WirelessModemFake, IODriverSimulator};
+    ///
+    /// let simulator = NetworkSimulator::new(5);
+    /// simulator.create_ether("1");
+    ///
+    /// let sender = WirelessModemFake::new("sender");
+    /// let receiver = WirelessModemFake::new("receiver");
+    /// simulator.get_ether("1").unwrap().register_driver(sender.clone());
+    /// simulator.get_ether("1").unwrap().register_driver(receiver.clone());
+    ///
+    /// sender.put_to_rx_pin(b'a');
+    /// simulator.tick();
+    ///
+    /// assert_eq!(receiver.get_from_tx_pin(), Some(b'a'));
+    /// assert_eq!(simulator.get_current_tick(), 1);
+    /// assert_eq!(simulator.get_virtual_time_ms(), 5);
+    /// ```
+    pub fn tick(&self) {
+        #[cfg(feature = "tracing")]
+        let _tick_span = self.tick_span().entered();
+
+        self.apply_scheduled_faults();
+
+        let now = self.get_virtual_time_ms();
+        for registered in self.applications.borrow_mut().iter_mut() {
+            #[cfg(feature = "tracing")]
+            let _device_span = registered.modem.span().entered();
+
+            if !registered.modem.is_powered() {
+                continue;
+            }
+
+            let power_cycle_count = registered.modem.get_power_cycle_count();
+            if registered.seen_power_cycle_count != Some(power_cycle_count) {
+                registered.application.reboot();
+                registered.seen_power_cycle_count = Some(power_cycle_count);
+            }
+            registered.application.update(&mut registered.modem, now);
+        }
+
+        self.start_tick();
+        self.simulate();
+        self.end_tick();
     }
 
+    /// Runs given count of whole ticks.
+    pub fn run_ticks(&self, count: u64) {
+        for _ in 0..count {
+            self.tick();
+        }
+    }
+
+    /// Gets the number of ticks already simulated.
+    pub fn get_current_tick(&self) -> u64 {
+        *self
+            .tick_count
+            .lock()
+            .expect("Fail to get lock on tick count")
+    }
+
+    /// Gets the simulated time in milliseconds, which is the count of
+    /// simulated ticks multiplied by `ms_per_tick`.
+    pub fn get_virtual_time_ms(&self) -> u64 {
+        self.get_current_tick() * self.ms_per_tick
+    }
+
+    /// Creates the span of the tick which is going to be simulated next.
+    /// Enter it while updating protocol code to get its logs nested under the right tick.
+    /// ```
+    /// use proto_lab::NetworkSimulator;
+    ///
+    /// let simulator = NetworkSimulator::new(1);
+    /// {
+    ///     let _tick_span = simulator.tick_span().entered();
+    ///     // Update nodes here.
+    /// }
+    /// simulator.tick();
+    /// ```
+    #[cfg(feature = "tracing")]
+    pub fn tick_span(&self) -> tracing::Span {
+        let tick = self.get_current_tick();
+        tracing::info_span!("tick", tick, virtual_time_ms = tick * self.ms_per_tick)
+    }
+
+    /// Starts simulation of ticks in the background thread.
+    /// Registered applications can only be run in lock-step by `tick`,
+    /// so starting the thread with any application registered panics.
+    /// Scheduled faults are applied only by `tick` as well.
     pub fn start_simulation_thread(&mut self) {
+        if !self.applications.borrow().is_empty() {
+            panic!("Applications are registered. They can only be run by tick")
+        }
         match self.simulation_thread_handle {
             Some(_) => panic!("Simulation thread is already started"),
             None => {
                 let mut ethers = self.ethers.take().unwrap();
+                let media = self.media.take().unwrap();
 
                 let ms_per_tick = self.ms_per_tick;
                 let thread_killer_clone = Arc::clone(&self.thread_killer);
+                let tick_count_clone = Arc::clone(&self.tick_count);
+                let probes_clone = Arc::clone(&self.probes);
 
                 *self
                     .thread_killer
@@ -113,17 +452,51 @@ impl NetworkSimulator {
                             break;
                         }
                         std::thread::sleep(std::time::Duration::from_millis(ms_per_tick));
+
+                        let mut tick_count = tick_count_clone
+                            .lock()
+                            .expect("Fail to get lock on clonned tick count");
+
+                        #[cfg(feature = "tracing")]
+                        let _tick_span = tracing::info_span!(
+                            "tick",
+                            tick = *tick_count,
+                            virtual_time_ms = *tick_count * ms_per_tick
+                        )
+                        .entered();
+
                         for ether in ethers.iter_mut() {
                             ether.start_tick();
                         }
+                        for medium in media.iter() {
+                            medium.start_tick();
+                        }
                         for ether in ethers.iter_mut() {
                             ether.simulate();
                         }
+                        for medium in media.iter() {
+                            medium.simulate();
+                        }
                         for ether in ethers.iter_mut() {
                             ether.end_tick();
                         }
+                        for medium in media.iter() {
+                            medium.end_tick();
+                        }
+
+                        *tick_count += 1;
+                        let tick = *tick_count;
+                        // Probes read the tick count while creating messages.
+                        drop(tick_count);
+                        for probe in probes_clone
+                            .lock()
+                            .expect("Fail to get lock on clonned probes")
+                            .iter()
+                        {
+                            probe.scan(tick);
+                        }
                     }
-                    ethers
+                    (ethers, media)
                 }));
             }
         }
@@ -137,11 +510,11 @@ impl NetworkSimulator {
                     .thread_killer
                     .lock()
                     .expect("Fail to get lock on thread killer") = true;
-                self.ethers.replace(Some(
-                    simulation_thread_handle
-                        .join()
-                        .expect(" Fail to join simulation thread to get ethers back"),
-                ));
+                let (ethers, media) = simulation_thread_handle
+                    .join()
+                    .expect(" Fail to join simulation thread to get ethers back");
+                self.ethers.replace(Some(ethers));
+                self.media.replace(Some(media));
                 None
             }
         };
@@ -0,0 +1,213 @@
+use std::sync::{Arc, Mutex};
+
+use crate::{IODriverSimulator, MediumSimulator, Rs485TransceiverFake};
+
+/// RS-485 multi-drop bus. Every transceiver with enabled receiver gets the byte driven on the bus,
+/// including the driving one. When several transceivers drive the bus at once,
+/// everyone receives corrupted byte.
+pub struct Rs485Bus {
+    name: String,
+    transceivers: Arc<Mutex<Vec<Rs485TransceiverFake>>>,
+    contention_count: Arc<Mutex<u64>>,
+}
+
+impl Rs485Bus {
+    pub fn new(name: &str) -> Self {
+        Rs485Bus {
+            name: String::from(name),
+            transceivers: Arc::new(Mutex::new(Vec::new())),
+            contention_count: Arc::new(Mutex::new(0)),
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Connects the transceiver to the bus.
+    pub fn register_driver(&mut self, driver: Rs485TransceiverFake) {
+        let mut transceivers = self
+            .transceivers
+            .lock()
+            .expect("Fail to get lock on transceivers");
+        transceivers.push(driver);
+    }
+
+    /// Disconnects all the transceivers with the name from the bus.
+    pub fn unregister_driver(&mut self, name: &str) {
+        let mut transceivers = self
+            .transceivers
+            .lock()
+            .expect("Fail to get lock on transceivers");
+        transceivers.retain(|transceiver| transceiver.get_name() != name);
+    }
+
+    /// Gets connected transceiver.
+    pub fn get_driver(&self, name: &str) -> Option<Rs485TransceiverFake> {
+        let transceivers = self
+            .transceivers
+            .lock()
+            .expect("Fail to get lock on transceivers");
+        transceivers
+            .iter()
+            .find(|transceiver| transceiver.get_name() == name)
+            .cloned()
+    }
+
+    /// Gets count of ticks, during which several transceivers drove the bus at once.
+    pub fn get_contention_count(&self) -> u64 {
+        *self
+            .contention_count
+            .lock()
+            .expect("Fail to get lock on contention count")
+    }
+}
+
+impl MediumSimulator for Rs485Bus {
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    fn start_tick(&self) {
+        let transceivers = self
+            .transceivers
+            .lock()
+            .expect("Fail to get lock on transceivers");
+        for transceiver in transceivers.iter() {
+            transceiver.start_tick();
+        }
+    }
+
+    /// Drivers fight on the bus, so the result is the wired AND of the driven bytes.
+    /// ```
+    /// use proto_lab::{IODriverSimulator, MediumSimulator, Rs485Bus, Rs485TransceiverFake};
+    ///
+    /// let mut bus = Rs485Bus::new("bus");
+    /// let a = Rs485TransceiverFake::new("a");
+    /// let b = Rs485TransceiverFake::new("b");
+    /// let c = Rs485TransceiverFake::new("c");
+    /// for transceiver in [&a, &b, &c] {
+    ///     bus.register_driver(transceiver.clone());
+    /// }
+    ///
+    /// a.set_driver_enable(true);
+    /// a.put_to_rx_pin(0b1100);
+    /// b.set_driver_enable(true);
+    /// b.put_to_rx_pin(0b1010);
+    ///
+    /// bus.start_tick();
+    /// bus.simulate();
+    /// bus.end_tick();
+    ///
+    /// assert_eq!(c.get_from_tx_pin(), Some(0b1000));
+    /// assert_eq!(c.get_contention_count(), 1);
+    /// assert_eq!(bus.get_contention_count(), 1);
+    /// ```
+    fn simulate(&self) {
+        let transceivers = self
+            .transceivers
+            .lock()
+            .expect("Fail to get lock on transceivers");
+
+        let driven_bytes: Vec<u8> = transceivers
+            .iter()
+            .filter_map(|transceiver| transceiver.get_from_device_network_side())
+            .collect();
+
+        match driven_bytes.as_slice() {
+            [] => (),
+            [byte] => {
+                for transceiver in transceivers.iter() {
+                    transceiver.put_to_device_network_side(*byte);
+                }
+            }
+            _ => {
+                let corrupted_byte = driven_bytes.iter().fold(0xFF, |result, byte| result & byte);
+                for transceiver in transceivers.iter() {
+                    transceiver.put_contention_to_device_network_side(corrupted_byte);
+                }
+                *self
+                    .contention_count
+                    .lock()
+                    .expect("Fail to get lock on contention count") += 1;
+            }
+        }
+    }
+
+    fn end_tick(&self) {
+        let transceivers = self
+            .transceivers
+            .lock()
+            .expect("Fail to get lock on transceivers");
+        for transceiver in transceivers.iter() {
+            transceiver.end_tick();
+        }
+    }
+}
+
+impl Clone for Rs485Bus {
+    fn clone(&self) -> Self {
+        Rs485Bus {
+            name: self.name.clone(),
+            transceivers: Arc::clone(&self.transceivers),
+            contention_count: Arc::clone(&self.contention_count),
+        }
+    }
+}
+
+#[cfg(test)]
+mod rs485_bus_tests {
+    use crate::{NetworkSimulator, Rs485Bus, Rs485TransceiverFake};
+
+    #[test]
+    fn test_releasing_de_too_early_truncates_frame() {
+        let simulator = NetworkSimulator::new(1);
+        let mut bus = Rs485Bus::new("bus");
+        let master = Rs485TransceiverFake::new("master");
+        let slave = Rs485TransceiverFake::new("slave");
+        bus.register_driver(master.clone());
+        bus.register_driver(slave.clone());
+        simulator.add_medium(bus);
+
+        master.set_receiver_enable(false);
+        master.set_driver_enable(true);
+        master.write(b"abc").unwrap();
+        simulator.run_ticks(2);
+        master.set_driver_enable(false);
+        simulator.run_ticks(2);
+
+        let mut buf = [0u8; 8];
+        assert_eq!(slave.read(&mut buf).unwrap(), 2);
+        assert_eq!(&buf[..2], b"ab");
+        assert!(master.is_transmission_complete());
+        assert_eq!(slave.get_idle_ticks(), 2);
+        assert_eq!(master.read(&mut buf).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_two_drivers_at_once_collide() {
+        let simulator = NetworkSimulator::new(1);
+        let mut bus = Rs485Bus::new("bus");
+        let a = Rs485TransceiverFake::new("a");
+        let b = Rs485TransceiverFake::new("b");
+        let listener = Rs485TransceiverFake::new("listener");
+        for transceiver in [&a, &b, &listener] {
+            bus.register_driver(transceiver.clone());
+        }
+        simulator.add_medium(bus.clone());
+
+        a.set_driver_enable(true);
+        b.set_driver_enable(true);
+        a.write(&[0b1100_0011]).unwrap();
+        b.write(&[0b1010_0101]).unwrap();
+        simulator.run_ticks(2);
+
+        for transceiver in [&a, &b, &listener] {
+            let mut buf = [0u8; 4];
+            assert_eq!(transceiver.read(&mut buf).unwrap(), 1);
+            assert_eq!(buf[0], 0b1000_0001);
+            assert_eq!(transceiver.get_contention_count(), 1);
+        }
+        assert_eq!(bus.get_contention_count(), 1);
+    }
+}
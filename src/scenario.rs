@@ -0,0 +1,345 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{IODriverSimulator, NetworkSimulator, WirelessModemFake};
+
+/// Action, which is executed by the scenario right before the tick it is scheduled at.
+pub enum ScenarioAction {
+    /// Writes bytes into the RX pin of the device.
+    Write { device: String, bytes: Vec<u8> },
+    /// Registers the device in the ether.
+    RegisterDriver { ether: String, device: String },
+    /// Unregisters the device from the ether.
+    UnregisterDriver { ether: String, device: String },
+}
+
+enum Condition {
+    Received { device: String, bytes: Vec<u8> },
+    Custom(Box<dyn FnMut(&NetworkSimulator) -> bool>),
+}
+
+struct Expectation {
+    description: String,
+    deadline: u64,
+    condition: Condition,
+}
+
+/// Outcome of a single expectation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpectationResult {
+    pub description: String,
+    pub passed: bool,
+    /// Scenario tick, at which the expectation was evaluated for the last time.
+    pub tick: u64,
+}
+
+/// Outcome of the whole scenario.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScenarioReport {
+    pub results: Vec<ExpectationResult>,
+}
+
+impl ScenarioReport {
+    /// Tells if all the expectations passed.
+    pub fn is_success(&self) -> bool {
+        self.results.iter().all(|result| result.passed)
+    }
+
+    /// Gets the expectations, which failed.
+    pub fn get_failures(&self) -> Vec<&ExpectationResult> {
+        self.results
+            .iter()
+            .filter(|result| !result.passed)
+            .collect()
+    }
+}
+
+/// Timeline of actions and expectations, which is run against `NetworkSimulator`.
+/// Ticks of the scenario are counted from the moment it is run.
+/// Devices are referred by name. Devices registered in any ether of the simulator
+/// are found automatically, others shall be added with `device`.
+///
+/// Bytes on TX pins of the devices named in `expect_received_by` are read by the scenario itself,
+/// so such devices shall not be read by anything else.
+/// ```
+/// use proto_lab::{NetworkSimulator, Scenario, WirelessModemFake};
+///
+/// let simulator = NetworkSimulator::new(1);
+/// simulator.create_ether("air");
+/// for name in ["a", "b", "c"] {
+///     simulator.get_ether("air").unwrap().register_driver(WirelessModemFake::new(name));
+/// }
+///
+/// let report = Scenario::new()
+///     .write_at(10, "a", b"X")
+///     .unregister_at(5, "air", "b")
+///     .expect_received_by(20, "c", b"X")
+///     .expect_received_by(20, "b", b"X")
+///     .run(&simulator);
+///
+/// assert!(report.results[0].passed);
+/// assert_eq!(report.results[0].tick, 10);
+/// assert!(!report.results[1].passed);
+/// assert_eq!(report.results[1].tick, 20);
+/// ```
+#[derive(Default)]
+pub struct Scenario {
+    devices: Vec<WirelessModemFake>,
+    actions: BTreeMap<u64, Vec<ScenarioAction>>,
+    expectations: Vec<Expectation>,
+}
+
+impl Scenario {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the device, which is not registered in any ether yet.
+    pub fn device(mut self, device: WirelessModemFake) -> Self {
+        self.devices.push(device);
+        self
+    }
+
+    /// Schedules the action to be executed right before the tick.
+    pub fn at(mut self, tick: u64, action: ScenarioAction) -> Self {
+        self.actions.entry(tick).or_default().push(action);
+        self
+    }
+
+    /// Schedules writing of bytes into the RX pin of the device.
+    pub fn write_at(self, tick: u64, device: &str, bytes: &[u8]) -> Self {
+        self.at(
+            tick,
+            ScenarioAction::Write {
+                device: String::from(device),
+                bytes: bytes.to_vec(),
+            },
+        )
+    }
+
+    /// Schedules registration of the device in the ether.
+    pub fn register_at(self, tick: u64, ether: &str, device: &str) -> Self {
+        self.at(
+            tick,
+            ScenarioAction::RegisterDriver {
+                ether: String::from(ether),
+                device: String::from(device),
+            },
+        )
+    }
+
+    /// Schedules unregistration of the device from the ether.
+    pub fn unregister_at(self, tick: u64, ether: &str, device: &str) -> Self {
+        self.at(
+            tick,
+            ScenarioAction::UnregisterDriver {
+                ether: String::from(ether),
+                device: String::from(device),
+            },
+        )
+    }
+
+    /// Expects the device to put the bytes on its TX pin not later than the tick.
+    pub fn expect_received_by(mut self, tick: u64, device: &str, bytes: &[u8]) -> Self {
+        self.expectations.push(Expectation {
+            description: format!("{} receives {:?} by tick {}", device, bytes, tick),
+            deadline: tick,
+            condition: Condition::Received {
+                device: String::from(device),
+                bytes: bytes.to_vec(),
+            },
+        });
+        self
+    }
+
+    /// Expects the condition to become true not later than the tick.
+    pub fn expect_by(
+        mut self,
+        tick: u64,
+        description: &str,
+        condition: impl FnMut(&NetworkSimulator) -> bool + 'static,
+    ) -> Self {
+        self.expectations.push(Expectation {
+            description: String::from(description),
+            deadline: tick,
+            condition: Condition::Custom(Box::new(condition)),
+        });
+        self
+    }
+
+    /// Runs the scenario until all the expectations are evaluated and all the actions are executed.
+    /// Panics if the scenario refers to unknown device or ether.
+    pub fn run(mut self, simulator: &NetworkSimulator) -> ScenarioReport {
+        let devices = self.collect_devices(simulator);
+
+        let last_tick = self
+            .actions
+            .keys()
+            .copied()
+            .chain(
+                self.expectations
+                    .iter()
+                    .map(|expectation| expectation.deadline),
+            )
+            .max();
+
+        let mut received: HashMap<String, Vec<u8>> = HashMap::new();
+        for expectation in self.expectations.iter() {
+            if let Condition::Received { ref device, .. } = expectation.condition {
+                if !devices.contains_key(device) {
+                    panic!("Scenario refers to unknown device {}", device);
+                }
+                received.insert(device.clone(), Vec::new());
+            }
+        }
+
+        let mut results: Vec<Option<ExpectationResult>> =
+            self.expectations.iter().map(|_| None).collect();
+
+        let Some(last_tick) = last_tick else {
+            return ScenarioReport {
+                results: Vec::new(),
+            };
+        };
+
+        for tick in 0..=last_tick {
+            for action in self.actions.remove(&tick).unwrap_or_default() {
+                Scenario::execute(action, simulator, &devices);
+            }
+
+            simulator.tick();
+
+            for (name, bytes) in received.iter_mut() {
+                while let Some(byte) = devices[name].get_from_tx_pin() {
+                    bytes.push(byte);
+                }
+            }
+
+            for (expectation, result) in self.expectations.iter_mut().zip(results.iter_mut()) {
+                if result.is_some() {
+                    continue;
+                }
+
+                let passed = match expectation.condition {
+                    Condition::Received {
+                        ref device,
+                        ref bytes,
+                    } => contains(&received[device], bytes),
+                    Condition::Custom(ref mut condition) => condition(simulator),
+                };
+
+                if passed || tick >= expectation.deadline {
+                    result.replace(ExpectationResult {
+                        description: expectation.description.clone(),
+                        passed,
+                        tick,
+                    });
+                }
+            }
+        }
+
+        ScenarioReport {
+            results: results
+                .into_iter()
+                .map(|result| result.expect("Every expectation is evaluated by its deadline"))
+                .collect(),
+        }
+    }
+
+    fn collect_devices(&self, simulator: &NetworkSimulator) -> HashMap<String, WirelessModemFake> {
+        let mut devices = HashMap::new();
+        for device in self.devices.iter() {
+            devices.insert(String::from(device.get_name()), device.clone());
+        }
+        for ether in simulator.get_ethers() {
+            for name in ether.get_driver_names() {
+                if let Some(device) = ether.get_driver(&name) {
+                    devices.entry(name).or_insert(device);
+                }
+            }
+        }
+        devices
+    }
+
+    fn execute(
+        action: ScenarioAction,
+        simulator: &NetworkSimulator,
+        devices: &HashMap<String, WirelessModemFake>,
+    ) {
+        let get_device = |name: &str| {
+            devices
+                .get(name)
+                .unwrap_or_else(|| panic!("Scenario refers to unknown device {}", name))
+        };
+        let get_ether = |name: &str| {
+            simulator
+                .get_ether(name)
+                .unwrap_or_else(|| panic!("Scenario refers to unknown ether {}", name))
+        };
+
+        match action {
+            ScenarioAction::Write { device, bytes } => {
+                for byte in bytes {
+                    get_device(&device).put_to_rx_pin(byte);
+                }
+            }
+            ScenarioAction::RegisterDriver { ether, device } => {
+                get_ether(&ether).register_driver(get_device(&device).clone());
+            }
+            ScenarioAction::UnregisterDriver { ether, device } => {
+                get_ether(&ether).unregister_driver(&device);
+            }
+        }
+    }
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    needle.is_empty()
+        || haystack
+            .windows(needle.len())
+            .any(|window| window == needle)
+}
+
+#[cfg(test)]
+mod scenario_tests {
+    use super::*;
+
+    #[test]
+    fn test_reregistered_device_receives_again() {
+        let simulator = NetworkSimulator::new(1);
+        simulator.create_ether("air");
+        simulator
+            .get_ether("air")
+            .unwrap()
+            .register_driver(WirelessModemFake::new("a"));
+        simulator
+            .get_ether("air")
+            .unwrap()
+            .register_driver(WirelessModemFake::new("b"));
+
+        let report = Scenario::new()
+            .unregister_at(0, "air", "b")
+            .write_at(1, "a", b"lost")
+            .register_at(10, "air", "b")
+            .write_at(10, "a", b"found")
+            .expect_received_by(30, "b", b"found")
+            .expect_by(30, "b is registered", |simulator| {
+                simulator
+                    .get_ether("air")
+                    .unwrap()
+                    .get_driver("b")
+                    .is_some()
+            })
+            .run(&simulator);
+
+        assert!(report.is_success(), "{:?}", report.get_failures());
+        assert_eq!(report.results[0].tick, 14);
+        assert_eq!(report.results[1].tick, 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "Scenario refers to unknown device x")]
+    fn test_unknown_device_panics() {
+        let simulator = NetworkSimulator::new(1);
+        Scenario::new().write_at(0, "x", b"a").run(&simulator);
+    }
+}
@@ -0,0 +1,228 @@
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    device::LineSymbol,
+    medium::DEFAULT_MS_PER_TICK,
+    rng::Rng,
+    uart::{decode, encode, encode_break},
+    MediumSimulator, SerialPortFake,
+};
+
+struct LinkState {
+    /// Bit time credit of each direction, in units of bits * 1000.
+    credits: [i64; 2],
+    ms_per_tick: u64,
+    noise: Option<(f64, Rng)>,
+}
+
+/// Full duplex point to point link, like a UART cable with crossed TX and RX wires.
+/// Each direction sends as many frames per tick, as the baud rate and line config
+/// of the sending port allow. Receiving port decodes them with its own baud rate and line config.
+pub struct SerialLink {
+    name: String,
+    ports: [SerialPortFake; 2],
+    state: Arc<Mutex<LinkState>>,
+}
+
+impl SerialLink {
+    /// Connects two ports. Baud timing uses the tick duration of the simulator, the link is added to.
+    /// ```
+    /// use proto_lab::{NetworkSimulator, SerialLink, SerialPortFake};
+    ///
+    /// let simulator = NetworkSimulator::new(1);
+    /// let a = SerialPortFake::new("a", 9600);
+    /// let b = SerialPortFake::new("b", 9600);
+    /// simulator.add_medium(SerialLink::new("cable", a.clone(), b.clone()));
+    ///
+    /// a.write(b"ping").unwrap();
+    /// b.write(b"pong").unwrap();
+    /// // 9600 baud sends 0.96 byte per millisecond.
+    /// simulator.run_ticks(5);
+    ///
+    /// let mut buf = [0u8; 4];
+    /// assert_eq!(b.read(&mut buf).unwrap(), 4);
+    /// assert_eq!(&buf, b"ping");
+    /// assert_eq!(a.read(&mut buf).unwrap(), 4);
+    /// assert_eq!(&buf, b"pong");
+    /// ```
+    pub fn new(name: &str, a: SerialPortFake, b: SerialPortFake) -> Self {
+        SerialLink {
+            name: String::from(name),
+            ports: [a, b],
+            state: Arc::new(Mutex::new(LinkState {
+                credits: [0, 0],
+                ms_per_tick: DEFAULT_MS_PER_TICK,
+                noise: None,
+            })),
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Flips every bit on the line with the probability of `bit_error_rate`.
+    /// Same seed gives the same noise.
+    pub fn set_noise(&self, bit_error_rate: f64, seed: u64) {
+        self.state
+            .lock()
+            .expect("Fail to get lock on link state")
+            .noise = Some((bit_error_rate, Rng::new(seed)));
+    }
+
+    pub fn remove_noise(&self) {
+        self.state
+            .lock()
+            .expect("Fail to get lock on link state")
+            .noise = None;
+    }
+}
+
+impl MediumSimulator for SerialLink {
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    fn set_ms_per_tick(&self, ms_per_tick: u64) {
+        self.state
+            .lock()
+            .expect("Fail to get lock on link state")
+            .ms_per_tick = ms_per_tick;
+    }
+
+    /// Bytes are sent within `simulate`.
+    fn start_tick(&self) {}
+
+    fn simulate(&self) {
+        let mut state = self.state.lock().expect("Fail to get lock on link state");
+        let LinkState {
+            credits,
+            ms_per_tick,
+            noise,
+        } = &mut *state;
+
+        for (direction, credit) in credits.iter_mut().enumerate() {
+            let sender = &self.ports[direction];
+            let receiver = &self.ports[1 - direction];
+
+            // Idle line does not save time for later bytes.
+            if !sender.has_line_side_data() {
+                *credit = (*credit).min(0);
+                continue;
+            }
+
+            let tx_baud = sender.get_baud();
+            let rx_baud = receiver.get_baud();
+            let tx_config = sender.get_line_config();
+            let rx_config = receiver.get_line_config();
+            let frame_credit = tx_config.get_frame_bits() as i64 * 1000;
+            *credit += tx_baud as i64 * *ms_per_tick as i64;
+
+            while *credit >= frame_credit {
+                let mut bits = match sender.take_from_line_side() {
+                    Some(LineSymbol::Byte(byte)) => encode(byte, &tx_config),
+                    Some(LineSymbol::Break) => encode_break(&tx_config),
+                    None => break,
+                };
+                *credit -= bits.len() as i64 * 1000;
+
+                if let Some((bit_error_rate, rng)) = noise {
+                    for bit in bits.iter_mut() {
+                        if rng.next_f64() < *bit_error_rate {
+                            *bit = !*bit;
+                        }
+                    }
+                }
+
+                receiver.put_to_line_side(decode(&bits, tx_baud, rx_baud, &rx_config));
+            }
+        }
+    }
+
+    /// Bytes are sent within `simulate`.
+    fn end_tick(&self) {}
+}
+
+impl Clone for SerialLink {
+    fn clone(&self) -> Self {
+        SerialLink {
+            name: self.name.clone(),
+            ports: self.ports.clone(),
+            state: Arc::clone(&self.state),
+        }
+    }
+}
+
+#[cfg(test)]
+mod serial_link_tests {
+    use crate::{NetworkSimulator, SerialLink, SerialPortFake};
+
+    fn create_link(
+        a_baud: u32,
+        b_baud: u32,
+    ) -> (NetworkSimulator, SerialLink, SerialPortFake, SerialPortFake) {
+        let simulator = NetworkSimulator::new(1);
+        let a = SerialPortFake::new("a", a_baud);
+        let b = SerialPortFake::new("b", b_baud);
+        let link = SerialLink::new("link", a.clone(), b.clone());
+        simulator.add_medium(link.clone());
+        (simulator, link, a, b)
+    }
+
+    #[test]
+    fn test_link_takes_tick_duration_of_simulator() {
+        let simulator = NetworkSimulator::new(10);
+        let a = SerialPortFake::new("a", 115200);
+        let b = SerialPortFake::new("b", 115200);
+        simulator.add_medium(SerialLink::new("link", a.clone(), b.clone()));
+        a.write(&[0x55; 200]).unwrap();
+
+        simulator.run_ticks(1);
+        let mut buf = [0u8; 200];
+        assert_eq!(b.read(&mut buf).unwrap(), 115);
+    }
+
+    #[test]
+    fn test_baud_limits_throughput() {
+        let (simulator, _link, a, b) = create_link(115200, 115200);
+        a.write(&[0x55; 100]).unwrap();
+
+        simulator.run_ticks(1);
+        let mut buf = [0u8; 100];
+        assert_eq!(b.read(&mut buf).unwrap(), 11);
+
+        simulator.run_ticks(8);
+        assert_eq!(b.read(&mut buf).unwrap(), 89);
+        assert!(a.is_transmission_complete());
+    }
+
+    #[test]
+    fn test_mismatched_baud_gives_framing_errors() {
+        let (simulator, _link, a, b) = create_link(4800, 9600);
+        a.write(&[0x55; 4]).unwrap();
+
+        simulator.run_ticks(9);
+
+        assert_eq!(b.get_framing_error_count(), 4);
+        assert_eq!(a.get_framing_error_count(), 0);
+    }
+
+    #[test]
+    fn test_noise_is_reproducible() {
+        let received = |seed| {
+            let (simulator, link, a, b) = create_link(115200, 115200);
+            link.set_noise(0.05, seed);
+            a.write(&[0xA5; 64]).unwrap();
+            simulator.run_ticks(6);
+            let mut buf = [0u8; 64];
+            let mut count = 0;
+            while count < 64 {
+                count += b.read(&mut buf[count..]).unwrap_or(0);
+            }
+            buf
+        };
+
+        assert_eq!(received(7), received(7));
+        assert!(received(7).iter().any(|byte| *byte != 0xA5));
+    }
+}
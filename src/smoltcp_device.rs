@@ -0,0 +1,321 @@
+use smoltcp::{
+    phy::{self, DeviceCapabilities, Medium},
+    time::Instant,
+};
+
+use crate::{FrameDecoder, Framing, WirelessModemFake};
+
+/// Default MTU of the IP packets, small enough for slow radio links.
+pub const DEFAULT_SMOLTCP_MTU: usize = 256;
+
+/// Adapter, which lets smoltcp interface send IP packets over the modem.
+/// Packets are framed with SLIP, so the interfaces of all the modems in one ether
+/// talk to each other like being on the same link. Corrupted packets are dropped
+/// by the checksums of the IP stack.
+///
+/// The device accepts a packet to transmit only when the modem is not busy, so smoltcp
+/// keeps the packets in its socket buffers while the previous frame is still on the air
+/// or the modem is powered off.
+///
+/// Drive it from the simulation loop, using virtual time as the smoltcp timestamp:
+/// `iface.poll(Instant::from_millis(simulator.get_virtual_time_ms() as i64), &mut device, &mut sockets)`.
+pub struct SmoltcpDevice {
+    modem: WirelessModemFake,
+    mtu: usize,
+    decoder: FrameDecoder,
+    failed_write_count: u64,
+}
+
+impl SmoltcpDevice {
+    pub fn new(modem: WirelessModemFake) -> Self {
+        Self::with_mtu(modem, DEFAULT_SMOLTCP_MTU)
+    }
+
+    pub fn with_mtu(modem: WirelessModemFake, mtu: usize) -> Self {
+        SmoltcpDevice {
+            modem,
+            mtu,
+            decoder: FrameDecoder::with_max_frame_length(Framing::Slip, mtu),
+            failed_write_count: 0,
+        }
+    }
+
+    pub fn get_modem(&self) -> &WirelessModemFake {
+        &self.modem
+    }
+
+    /// Counts the packets, which the modem did not take, because it was powered off
+    /// when smoltcp handed the packet over. Such packets are lost.
+    pub fn get_failed_write_count(&self) -> u64 {
+        self.failed_write_count
+    }
+
+    /// Reads bytes from the modem until the whole packet is received.
+    fn receive_packet(&mut self) -> Option<Vec<u8>> {
+        let mut byte = [0u8];
+        while self.modem.read(&mut byte).unwrap_or(0) == 1 {
+            if let Some(packet) = self.decoder.push(byte[0]) {
+                return Some(packet);
+            }
+        }
+        None
+    }
+}
+
+impl phy::Device for SmoltcpDevice {
+    type RxToken<'a> = SmoltcpRxToken;
+    type TxToken<'a> = SmoltcpTxToken<'a>;
+
+    fn receive(&mut self, _timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+        let packet = self.receive_packet()?;
+        Some((
+            SmoltcpRxToken { packet },
+            SmoltcpTxToken {
+                modem: &self.modem,
+                failed_write_count: &mut self.failed_write_count,
+            },
+        ))
+    }
+
+    fn transmit(&mut self, _timestamp: Instant) -> Option<Self::TxToken<'_>> {
+        if self.modem.is_busy() {
+            return None;
+        }
+        Some(SmoltcpTxToken {
+            modem: &self.modem,
+            failed_write_count: &mut self.failed_write_count,
+        })
+    }
+
+    fn capabilities(&self) -> DeviceCapabilities {
+        let mut capabilities = DeviceCapabilities::default();
+        capabilities.medium = Medium::Ip;
+        capabilities.max_transmission_unit = self.mtu;
+        capabilities
+    }
+}
+
+pub struct SmoltcpRxToken {
+    packet: Vec<u8>,
+}
+
+impl phy::RxToken for SmoltcpRxToken {
+    fn consume<R, F>(self, f: F) -> R
+    where
+        F: FnOnce(&[u8]) -> R,
+    {
+        f(&self.packet)
+    }
+}
+
+pub struct SmoltcpTxToken<'a> {
+    modem: &'a WirelessModemFake,
+    failed_write_count: &'a mut u64,
+}
+
+impl phy::TxToken for SmoltcpTxToken<'_> {
+    fn consume<R, F>(self, len: usize, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        let mut packet = vec![0u8; len];
+        let result = f(&mut packet);
+
+        // The modem ignores the bytes written while it is powered off.
+        let frame = Framing::Slip.encode(&packet);
+        let accepted = self.modem.is_powered();
+        let Ok(written) = self.modem.write(&frame);
+        if !accepted || written < frame.len() {
+            *self.failed_write_count += 1;
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod smoltcp_device_tests {
+    use smoltcp::{
+        iface::{Config, Interface, SocketHandle, SocketSet},
+        phy::{Device, TxToken},
+        socket::udp,
+        time::Instant,
+        wire::{HardwareAddress, IpAddress, IpCidr, IpEndpoint},
+    };
+
+    use crate::{IODriverSimulator, NetworkSimulator, SmoltcpDevice, WirelessModemFake};
+
+    /// smoltcp interface with one UDP socket, bound to `10.0.0.<host>:<port>`.
+    struct UdpNode {
+        device: SmoltcpDevice,
+        iface: Interface,
+        sockets: SocketSet<'static>,
+        handle: SocketHandle,
+    }
+
+    impl UdpNode {
+        fn new(modem: WirelessModemFake, host: u8, port: u16) -> Self {
+            let mut device = SmoltcpDevice::new(modem);
+            let mut iface =
+                Interface::new(Config::new(HardwareAddress::Ip), &mut device, Instant::ZERO);
+            iface.update_ip_addrs(|addrs| {
+                addrs
+                    .push(IpCidr::new(IpAddress::v4(10, 0, 0, host), 24))
+                    .unwrap();
+            });
+
+            let mut sockets = SocketSet::new(vec![]);
+            let mut socket = udp::Socket::new(
+                udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY; 4], vec![0; 1024]),
+                udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY; 4], vec![0; 1024]),
+            );
+            socket.bind(port).unwrap();
+            let handle = sockets.add(socket);
+
+            UdpNode {
+                device,
+                iface,
+                sockets,
+                handle,
+            }
+        }
+
+        fn socket(&mut self) -> &mut udp::Socket<'static> {
+            self.sockets.get_mut::<udp::Socket>(self.handle)
+        }
+
+        fn poll(&mut self, simulator: &NetworkSimulator) {
+            let now = Instant::from_millis(simulator.get_virtual_time_ms() as i64);
+            self.iface.poll(now, &mut self.device, &mut self.sockets);
+        }
+    }
+
+    /// Polls both nodes and ticks the simulator until `receiver` gets a datagram.
+    fn run_until_received(
+        simulator: &NetworkSimulator,
+        sender: &mut UdpNode,
+        receiver: &mut UdpNode,
+        ticks: u64,
+    ) -> Option<(Vec<u8>, IpEndpoint)> {
+        for _ in 0..ticks {
+            sender.poll(simulator);
+            receiver.poll(simulator);
+
+            if receiver.socket().can_recv() {
+                let (payload, meta) = receiver.socket().recv().unwrap();
+                return Some((payload.to_vec(), meta.endpoint));
+            }
+            simulator.tick();
+        }
+        None
+    }
+
+    fn create_nodes(simulator: &NetworkSimulator) -> (UdpNode, UdpNode) {
+        simulator.create_ether("air");
+        let mut ether = simulator.get_ether("air").unwrap();
+        let modem_a = WirelessModemFake::new("a");
+        let modem_b = WirelessModemFake::new("b");
+        ether.register_driver(modem_a.clone());
+        ether.register_driver(modem_b.clone());
+
+        (
+            UdpNode::new(modem_a, 1, 1000),
+            UdpNode::new(modem_b, 2, 2000),
+        )
+    }
+
+    #[test]
+    fn test_udp_datagram_over_ether() {
+        let simulator = NetworkSimulator::new(1);
+        let (mut node_a, mut node_b) = create_nodes(&simulator);
+
+        node_a
+            .socket()
+            .send_slice(
+                b"hello over the air",
+                IpEndpoint::new(IpAddress::v4(10, 0, 0, 2), 2000),
+            )
+            .unwrap();
+
+        let (payload, endpoint) = run_until_received(&simulator, &mut node_a, &mut node_b, 200)
+            .expect("Datagram shall be delivered");
+        assert_eq!(payload, b"hello over the air");
+        assert_eq!(endpoint, IpEndpoint::new(IpAddress::v4(10, 0, 0, 1), 1000));
+        assert_eq!(node_a.device.get_failed_write_count(), 0);
+    }
+
+    #[test]
+    fn test_jammed_datagram_is_dropped() {
+        let simulator = NetworkSimulator::new(1);
+        let (mut node_a, mut node_b) = create_nodes(&simulator);
+        let jammer = WirelessModemFake::new("jammer");
+        simulator
+            .get_ether("air")
+            .unwrap()
+            .register_driver(jammer.clone());
+        simulator.register_application(jammer, |modem: &mut WirelessModemFake, _now: u64| {
+            while modem.get_from_tx_pin().is_some() {}
+            if !modem.is_busy() {
+                modem.put_to_rx_pin(0x55);
+            }
+        });
+
+        node_a
+            .socket()
+            .send_slice(
+                b"hello over the air",
+                IpEndpoint::new(IpAddress::v4(10, 0, 0, 2), 2000),
+            )
+            .unwrap();
+
+        assert_eq!(
+            run_until_received(&simulator, &mut node_a, &mut node_b, 200),
+            None
+        );
+        assert_eq!(node_a.socket().send_queue(), 0);
+        assert!(!node_a.device.get_modem().is_busy());
+    }
+
+    #[test]
+    fn test_powered_off_modem_holds_back_datagram() {
+        let simulator = NetworkSimulator::new(1);
+        let (mut node_a, mut node_b) = create_nodes(&simulator);
+        node_a.device.get_modem().power_off(false);
+
+        node_a
+            .socket()
+            .send_slice(
+                b"hello over the air",
+                IpEndpoint::new(IpAddress::v4(10, 0, 0, 2), 2000),
+            )
+            .unwrap();
+
+        assert!(node_a.device.transmit(Instant::ZERO).is_none());
+        assert_eq!(
+            run_until_received(&simulator, &mut node_a, &mut node_b, 50),
+            None
+        );
+        assert_ne!(node_a.socket().send_queue(), 0);
+
+        node_a.device.get_modem().power_on();
+        let (payload, _) = run_until_received(&simulator, &mut node_a, &mut node_b, 200)
+            .expect("Datagram shall be delivered after power on");
+        assert_eq!(payload, b"hello over the air");
+        assert_eq!(node_a.device.get_failed_write_count(), 0);
+    }
+
+    #[test]
+    fn test_write_to_modem_powered_off_after_transmit_is_counted() {
+        let modem = WirelessModemFake::new("a");
+        let mut device = SmoltcpDevice::new(modem.clone());
+        let token = device.transmit(Instant::ZERO).unwrap();
+        token.consume(4, |buffer| {
+            buffer.copy_from_slice(&[1, 2, 3, 4]);
+            modem.power_off(false);
+        });
+
+        assert_eq!(device.get_failed_write_count(), 1);
+        modem.power_on();
+        assert!(!modem.is_busy());
+    }
+}
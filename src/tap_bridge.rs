@@ -0,0 +1,281 @@
+use std::{
+    fmt,
+    fs::{File, OpenOptions},
+    io::{self, Read, Write},
+    os::unix::{
+        fs::OpenOptionsExt,
+        io::{AsRawFd, FromRawFd},
+    },
+    sync::{Arc, Mutex},
+};
+
+use crate::{EthernetFrame, EthernetNic, MediumSimulator, ETHERNET_HEADER_LENGTH, ETHERNET_MTU};
+
+/// Bridges the port of `EthernetSwitch` to the Linux TAP interface,
+/// so the host IP stack exchanges frames with the simulated NICs.
+/// Frames are moved between the interface and the NIC once per tick.
+///
+/// Creation of TAP interface requires `CAP_NET_ADMIN`, and entering the network namespace
+/// requires `CAP_SYS_ADMIN`.
+/// ```no_run
+/// use proto_lab::{EthernetNic, EthernetSwitch, MacAddress, NetworkSimulator, TapBridge};
+///
+/// let simulator = NetworkSimulator::new(1);
+/// let switch = EthernetSwitch::new("switch");
+/// let host_port = EthernetNic::new("host_port", MacAddress([2, 0, 0, 0, 0, 0xFE]));
+/// switch.connect(host_port.clone(), 100_000_000);
+/// simulator.add_medium(switch);
+///
+/// let bridge = TapBridge::new_in_namespace("bridge", "tap0", "/var/run/netns/lab", host_port)
+///     .expect("Fail to create TAP interface");
+/// simulator.add_medium(bridge);
+/// simulator.run_ticks(1000);
+/// ```
+pub struct TapBridge {
+    name: String,
+    interface_name: String,
+    tap: Arc<File>,
+    nic: EthernetNic,
+    io_error_count: Arc<Mutex<u64>>,
+}
+
+impl TapBridge {
+    /// Creates TAP interface in the namespace of the calling thread, brings it up
+    /// and bridges it to the NIC. The NIC is switched to promiscuous mode,
+    /// so all the frames reaching its port are passed to the interface.
+    pub fn new(name: &str, interface_name: &str, nic: EthernetNic) -> io::Result<Self> {
+        let tap = open_tap(interface_name)?;
+        nic.set_promiscuous(true);
+        Ok(TapBridge {
+            name: String::from(name),
+            interface_name: String::from(interface_name),
+            tap: Arc::new(tap),
+            nic,
+            io_error_count: Arc::new(Mutex::new(0)),
+        })
+    }
+
+    /// Same as `new`, but creates the interface in the network namespace, like `/var/run/netns/NAME`
+    /// created by `ip netns add NAME`. The calling thread returns to its namespace afterwards,
+    /// and if it can not, the error still carries the outcome of the creation.
+    pub fn new_in_namespace(
+        name: &str,
+        interface_name: &str,
+        namespace_path: &str,
+        nic: EthernetNic,
+    ) -> Result<Self, TapNamespaceError> {
+        let own_namespace =
+            File::open("/proc/thread-self/ns/net").map_err(TapNamespaceError::Io)?;
+        let namespace = File::open(namespace_path).map_err(TapNamespaceError::Io)?;
+
+        set_namespace(&namespace).map_err(TapNamespaceError::Io)?;
+        let result = Self::new(name, interface_name, nic);
+        match (set_namespace(&own_namespace), result) {
+            (Ok(()), Ok(bridge)) => Ok(bridge),
+            (Ok(()), Err(error)) => Err(TapNamespaceError::Io(error)),
+            (Err(error), result) => Err(TapNamespaceError::NotRestored { result, error }),
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_interface_name(&self) -> &str {
+        &self.interface_name
+    }
+
+    /// Gets count of failed reads and writes of the interface, other than interrupted ones,
+    /// which are retried, and blocked writes, which drop the frame without being counted.
+    /// Failed read ends reading for the tick, failed write drops the frame.
+    pub fn get_io_error_count(&self) -> u64 {
+        *self
+            .io_error_count
+            .lock()
+            .expect("Fail to lock interface error count")
+    }
+
+    fn count_io_error(&self, _error: io::Error) {
+        #[cfg(feature = "tracing")]
+        tracing::warn!(interface = %self.interface_name, error = %_error, "TAP interface error");
+
+        *self
+            .io_error_count
+            .lock()
+            .expect("Fail to lock interface error count") += 1;
+    }
+}
+
+/// Error of `TapBridge::new_in_namespace`.
+#[derive(Debug)]
+pub enum TapNamespaceError {
+    /// Interface is not created. The calling thread is in its own namespace.
+    Io(io::Error),
+    /// The calling thread failed to return to its own namespace, and stays in the namespace
+    /// of the interface. `result` is the outcome of the creation, so the bridge is not lost.
+    NotRestored {
+        result: io::Result<TapBridge>,
+        error: io::Error,
+    },
+}
+
+impl fmt::Display for TapNamespaceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TapNamespaceError::Io(error) => write!(f, "Fail to create TAP interface: {}", error),
+            TapNamespaceError::NotRestored { error, .. } => write!(
+                f,
+                "Fail to return the thread to its network namespace: {}",
+                error
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TapNamespaceError {}
+
+fn set_namespace(namespace: &File) -> io::Result<()> {
+    // Safety: file descriptor is valid for the duration of the call.
+    match unsafe { libc::setns(namespace.as_raw_fd(), libc::CLONE_NEWNET) } {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+fn interface_request(interface_name: &str) -> io::Result<libc::ifreq> {
+    if interface_name.is_empty() || interface_name.len() >= libc::IFNAMSIZ {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid interface name :{}", interface_name),
+        ));
+    }
+    // Safety: ifreq is plain old data, zeroed one is valid.
+    let mut request: libc::ifreq = unsafe { std::mem::zeroed() };
+    for (place, byte) in request.ifr_name.iter_mut().zip(interface_name.bytes()) {
+        *place = byte as libc::c_char;
+    }
+    Ok(request)
+}
+
+fn open_tap(interface_name: &str) -> io::Result<File> {
+    let tap = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .custom_flags(libc::O_NONBLOCK)
+        .open("/dev/net/tun")?;
+
+    let mut request = interface_request(interface_name)?;
+    request.ifr_ifru.ifru_flags = (libc::IFF_TAP | libc::IFF_NO_PI) as libc::c_short;
+    // Safety: request lives for the duration of the call, and has the layout expected by TUNSETIFF.
+    if unsafe { libc::ioctl(tap.as_raw_fd(), libc::TUNSETIFF as _, &mut request) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    set_interface_up(interface_name)?;
+    Ok(tap)
+}
+
+fn set_interface_up(interface_name: &str) -> io::Result<()> {
+    // Safety: plain system call without pointers.
+    let socket = unsafe { libc::socket(libc::AF_INET, libc::SOCK_DGRAM, 0) };
+    if socket < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // Safety: socket is the fresh valid descriptor, from now on owned and closed by File.
+    let socket = unsafe { File::from_raw_fd(socket) };
+
+    let mut request = interface_request(interface_name)?;
+    // Safety: request lives for the duration of the calls, and has the layout expected by them.
+    unsafe {
+        if libc::ioctl(socket.as_raw_fd(), libc::SIOCGIFFLAGS as _, &mut request) < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        request.ifr_ifru.ifru_flags |= libc::IFF_UP as libc::c_short;
+        if libc::ioctl(socket.as_raw_fd(), libc::SIOCSIFFLAGS as _, &mut request) < 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+impl MediumSimulator for TapBridge {
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Frames are moved within `simulate`.
+    fn start_tick(&self) {}
+
+    fn simulate(&self) {
+        let mut buf = [0u8; ETHERNET_HEADER_LENGTH + ETHERNET_MTU];
+        loop {
+            match (&*self.tap).read(&mut buf) {
+                Ok(count) => {
+                    // Oversized frames, like ones with VLAN tag, are not supported.
+                    if let Some(frame) = EthernetFrame::from_bytes(&buf[..count]) {
+                        self.nic.send(frame);
+                    }
+                }
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => break,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(error) => {
+                    self.count_io_error(error);
+                    break;
+                }
+            }
+        }
+
+        while let Some(frame) = self.nic.receive() {
+            // Frames are dropped if the interface is down, as the real cable would do.
+            let bytes = frame.to_bytes();
+            // Frames not fitting into the interface queue are dropped like by the full queue.
+            loop {
+                match (&*self.tap).write(&bytes) {
+                    Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(error) if error.kind() == io::ErrorKind::WouldBlock => (),
+                    Err(error) => self.count_io_error(error),
+                    Ok(_) => (),
+                }
+                break;
+            }
+        }
+    }
+
+    /// Frames are moved within `simulate`.
+    fn end_tick(&self) {}
+}
+
+impl fmt::Debug for TapBridge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TapBridge")
+            .field("name", &self.name)
+            .field("interface_name", &self.interface_name)
+            .finish()
+    }
+}
+
+impl Clone for TapBridge {
+    fn clone(&self) -> Self {
+        TapBridge {
+            name: self.name.clone(),
+            interface_name: self.interface_name.clone(),
+            tap: Arc::clone(&self.tap),
+            nic: self.nic.clone(),
+            io_error_count: Arc::clone(&self.io_error_count),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tap_bridge_tests {
+    use crate::{EthernetNic, MacAddress, TapBridge};
+
+    #[test]
+    #[ignore = "requires CAP_NET_ADMIN and /dev/net/tun"]
+    fn test_tap_interface_is_created() {
+        let nic = EthernetNic::new("host_port", MacAddress([2, 0, 0, 0, 0, 0xFE]));
+        let bridge = TapBridge::new("bridge", "protolab0", nic).expect("Fail to create TAP");
+        assert!(std::path::Path::new("/sys/class/net/protolab0").exists());
+        assert_eq!(bridge.get_interface_name(), "protolab0");
+    }
+}
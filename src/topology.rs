@@ -0,0 +1,181 @@
+use crate::{rng::Rng, NetworkSimulator, WirelessModemFake};
+
+/// Common network shapes, which can be generated by `NetworkSimulator::generate_topology`.
+/// Devices are numbered from 0, and every pair of neighbours shares its own ether.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Topology {
+    /// Each device hears previous and next one.
+    Line(usize),
+    /// Same as line, but the last device also hears the first one.
+    Ring(usize),
+    /// Device 0 is the hub, which hears all the others.
+    Star(usize),
+    /// Devices are placed row by row, each hears its horizontal and vertical neighbours.
+    Grid { width: usize, height: usize },
+    /// Devices are placed randomly in the unit square,
+    /// each hears all the devices which are not farther than `radius`.
+    RandomGeometric {
+        count: usize,
+        radius: f64,
+        seed: u64,
+    },
+}
+
+impl Topology {
+    /// Gets the count of devices in the topology.
+    pub fn device_count(&self) -> usize {
+        match *self {
+            Topology::Line(count)
+            | Topology::Ring(count)
+            | Topology::Star(count)
+            | Topology::RandomGeometric { count, .. } => count,
+            Topology::Grid { width, height } => width * height,
+        }
+    }
+
+    /// Gets pairs of device indexes, which hear each other.
+    /// ```
+    /// use proto_lab::Topology;
+    ///
+    /// assert_eq!(Topology::Line(3).edges(), vec![(0, 1), (1, 2)]);
+    /// assert_eq!(Topology::Ring(3).edges(), vec![(0, 1), (1, 2), (2, 0)]);
+    /// assert_eq!(Topology::Star(3).edges(), vec![(0, 1), (0, 2)]);
+    /// ```
+    pub fn edges(&self) -> Vec<(usize, usize)> {
+        match *self {
+            Topology::Line(count) => (1..count).map(|i| (i - 1, i)).collect(),
+            Topology::Ring(count) => {
+                let mut edges = Topology::Line(count).edges();
+                if count > 2 {
+                    edges.push((count - 1, 0));
+                }
+                edges
+            }
+            Topology::Star(count) => (1..count).map(|i| (0, i)).collect(),
+            Topology::Grid { width, height } => {
+                let mut edges = Vec::new();
+                for row in 0..height {
+                    for column in 0..width {
+                        let i = row * width + column;
+                        if column + 1 < width {
+                            edges.push((i, i + 1));
+                        }
+                        if row + 1 < height {
+                            edges.push((i, i + width));
+                        }
+                    }
+                }
+                edges
+            }
+            Topology::RandomGeometric {
+                count,
+                radius,
+                seed,
+            } => {
+                let mut rng = Rng::new(seed);
+                let positions: Vec<(f64, f64)> = (0..count)
+                    .map(|_| (rng.next_f64(), rng.next_f64()))
+                    .collect();
+
+                let mut edges = Vec::new();
+                for i in 0..count {
+                    for j in (i + 1)..count {
+                        let (dx, dy) = (
+                            positions[i].0 - positions[j].0,
+                            positions[i].1 - positions[j].1,
+                        );
+                        if (dx * dx + dy * dy).sqrt() <= radius {
+                            edges.push((i, j));
+                        }
+                    }
+                }
+                edges
+            }
+        }
+    }
+}
+
+impl NetworkSimulator {
+    /// Creates devices named `<prefix><index>` and wires them into the topology.
+    /// Every pair of neighbours gets its own ether named `<device>-<device>`.
+    /// Returns the devices in order of their indexes.
+    /// ```
+    /// use proto_lab::{IODriverSimulator, NetworkSimulator, Topology};
+    ///
+    /// let simulator = NetworkSimulator::new(1);
+    /// let devices = simulator.generate_topology(&Topology::Line(3), "node_");
+    ///
+    /// assert_eq!(devices[1].get_name(), "node_1");
+    /// assert!(simulator.get_ether("node_1-node_2").is_some());
+    /// assert!(simulator.get_ether("node_0-node_2").is_none());
+    /// ```
+    pub fn generate_topology(&self, topology: &Topology, prefix: &str) -> Vec<WirelessModemFake> {
+        let devices: Vec<WirelessModemFake> = (0..topology.device_count())
+            .map(|i| WirelessModemFake::new(&format!("{}{}", prefix, i)))
+            .collect();
+
+        for (a, b) in topology.edges() {
+            let ether_name = format!("{}{}-{}{}", prefix, a, prefix, b);
+            self.create_ether(&ether_name);
+
+            let mut ether = self.get_ether(&ether_name).expect("Ether is just created");
+            ether.register_driver(devices[a].clone());
+            ether.register_driver(devices[b].clone());
+        }
+
+        devices
+    }
+}
+
+#[cfg(test)]
+mod topology_tests {
+    use super::*;
+    use crate::IODriverSimulator;
+
+    #[test]
+    fn test_grid_edges() {
+        let edges = Topology::Grid {
+            width: 3,
+            height: 2,
+        }
+        .edges();
+
+        assert_eq!(edges.len(), 7);
+        assert!(edges.contains(&(1, 4)));
+        assert!(!edges.contains(&(2, 3)));
+    }
+
+    #[test]
+    fn test_random_geometric_is_reproducible() {
+        let topology = Topology::RandomGeometric {
+            count: 20,
+            radius: 0.3,
+            seed: 42,
+        };
+
+        assert_eq!(topology.edges(), topology.edges());
+        assert_eq!(
+            Topology::RandomGeometric {
+                count: 5,
+                radius: 2.0,
+                seed: 1
+            }
+            .edges()
+            .len(),
+            10
+        );
+    }
+
+    #[test]
+    fn test_ring_relays_only_to_neighbours() {
+        let simulator = NetworkSimulator::new(1);
+        let devices = simulator.generate_topology(&Topology::Ring(4), "");
+
+        devices[0].put_to_rx_pin(b'a');
+        simulator.tick();
+
+        assert_eq!(devices[1].get_from_tx_pin(), Some(b'a'));
+        assert_eq!(devices[3].get_from_tx_pin(), Some(b'a'));
+        assert_eq!(devices[2].get_from_tx_pin(), None);
+    }
+}
@@ -0,0 +1,220 @@
+use std::collections::{BTreeSet, VecDeque};
+
+use crate::NetworkSimulator;
+
+/// Snapshot of who can hear whom. Devices hear each other when they share at least one ether.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Adjacency {
+    devices: Vec<String>,
+    neighbours: Vec<BTreeSet<usize>>,
+}
+
+impl Adjacency {
+    /// Gets names of all devices in order of their first appearance in ethers.
+    pub fn get_devices(&self) -> &[String] {
+        &self.devices
+    }
+
+    /// Gets names of devices, which share an ether with the given device.
+    pub fn get_neighbours(&self, name: &str) -> Option<Vec<&str>> {
+        let index = self.index_of(name)?;
+        Some(
+            self.neighbours[index]
+                .iter()
+                .map(|&neighbour| self.devices[neighbour].as_str())
+                .collect(),
+        )
+    }
+
+    /// Tells if both devices share an ether.
+    pub fn are_neighbours(&self, a: &str, b: &str) -> bool {
+        match (self.index_of(a), self.index_of(b)) {
+            (Some(a), Some(b)) => self.neighbours[a].contains(&b),
+            _ => false,
+        }
+    }
+
+    /// Gets adjacency matrix, which is indexed in order of `get_devices`.
+    pub fn to_matrix(&self) -> Vec<Vec<bool>> {
+        self.neighbours
+            .iter()
+            .map(|neighbours| {
+                (0..self.devices.len())
+                    .map(|i| neighbours.contains(&i))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Tells if every device can reach every other one, probably over several hops.
+    pub fn is_connected(&self) -> bool {
+        if self.devices.is_empty() {
+            return true;
+        }
+
+        let mut visited = vec![false; self.devices.len()];
+        let mut queue = VecDeque::from([0]);
+        visited[0] = true;
+
+        while let Some(current) = queue.pop_front() {
+            for &neighbour in self.neighbours[current].iter() {
+                if !visited[neighbour] {
+                    visited[neighbour] = true;
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+
+        visited.iter().all(|&is_visited| is_visited)
+    }
+
+    fn index_of(&self, name: &str) -> Option<usize> {
+        self.devices.iter().position(|device| device == name)
+    }
+}
+
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+impl NetworkSimulator {
+    /// Gets current adjacency of the devices.
+    /// ```
+    /// use proto_lab::{NetworkSimulator, Topology};
+    ///
+    /// let simulator = NetworkSimulator::new(1);
+    /// simulator.generate_topology(&Topology::Line(3), "");
+    ///
+    /// let adjacency = simulator.get_adjacency();
+    /// assert!(adjacency.are_neighbours("0", "1"));
+    /// assert!(!adjacency.are_neighbours("0", "2"));
+    /// assert!(adjacency.is_connected());
+    /// ```
+    pub fn get_adjacency(&self) -> Adjacency {
+        let mut adjacency = Adjacency {
+            devices: Vec::new(),
+            neighbours: Vec::new(),
+        };
+
+        for ether in self.get_ethers() {
+            let members: Vec<usize> = ether
+                .get_driver_names()
+                .into_iter()
+                .map(|name| match adjacency.index_of(&name) {
+                    Some(index) => index,
+                    None => {
+                        adjacency.devices.push(name);
+                        adjacency.neighbours.push(BTreeSet::new());
+                        adjacency.devices.len() - 1
+                    }
+                })
+                .collect();
+
+            for &a in members.iter() {
+                for &b in members.iter() {
+                    if a != b {
+                        adjacency.neighbours[a].insert(b);
+                    }
+                }
+            }
+        }
+
+        adjacency
+    }
+
+    /// Exports current topology in Graphviz DOT format.
+    /// Ethers are drawn as boxes, devices as ellipses connected to every ether they are registered in.
+    /// ```
+    /// use proto_lab::{NetworkSimulator, WirelessModemFake};
+    ///
+    /// let simulator = NetworkSimulator::new(1);
+    /// simulator.create_ether("air");
+    /// simulator.get_ether("air").unwrap().register_driver(WirelessModemFake::new("a"));
+    ///
+    /// let dot = simulator.to_dot();
+    /// assert!(dot.contains("\"ether:air\" -- \"device:a\";"));
+    /// ```
+    pub fn to_dot(&self) -> String {
+        let ethers = self.get_ethers();
+
+        let mut dot = String::from("graph network {\n");
+
+        for device in self.get_adjacency().get_devices() {
+            let device = escape_dot(device);
+            dot.push_str(&format!(
+                "    \"device:{}\" [label=\"{}\", shape=ellipse];\n",
+                device, device
+            ));
+        }
+
+        for ether in ethers.iter() {
+            let ether_name = escape_dot(ether.get_name());
+            dot.push_str(&format!(
+                "    \"ether:{}\" [label=\"{}\", shape=box];\n",
+                ether_name, ether_name
+            ));
+            for device in ether.get_driver_names() {
+                dot.push_str(&format!(
+                    "    \"ether:{}\" -- \"device:{}\";\n",
+                    ether_name,
+                    escape_dot(&device)
+                ));
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+#[cfg(test)]
+mod topology_export_tests {
+    use crate::{NetworkSimulator, Topology, WirelessModemFake};
+
+    #[test]
+    fn test_device_spanning_ethers_is_single_node() {
+        let simulator = NetworkSimulator::new(1);
+        simulator.create_ether("1");
+        simulator.create_ether("2");
+
+        let relay = WirelessModemFake::new("relay");
+        simulator
+            .get_ether("1")
+            .unwrap()
+            .register_driver(relay.clone());
+        simulator
+            .get_ether("2")
+            .unwrap()
+            .register_driver(relay.clone());
+        simulator
+            .get_ether("1")
+            .unwrap()
+            .register_driver(WirelessModemFake::new("a"));
+        simulator
+            .get_ether("2")
+            .unwrap()
+            .register_driver(WirelessModemFake::new("b"));
+
+        let adjacency = simulator.get_adjacency();
+        assert_eq!(adjacency.get_devices(), ["relay", "a", "b"]);
+        assert_eq!(adjacency.get_neighbours("relay"), Some(vec!["a", "b"]));
+        assert_eq!(
+            adjacency.to_matrix(),
+            vec![
+                vec![false, true, true],
+                vec![true, false, false],
+                vec![true, false, false]
+            ]
+        );
+        assert_eq!(simulator.to_dot().matches("\"device:relay\" [").count(), 1);
+    }
+
+    #[test]
+    fn test_disconnected_topology() {
+        let simulator = NetworkSimulator::new(1);
+        simulator.generate_topology(&Topology::Line(2), "left_");
+        simulator.generate_topology(&Topology::Line(2), "right_");
+
+        assert!(!simulator.get_adjacency().is_connected());
+    }
+}
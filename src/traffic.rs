@@ -0,0 +1,452 @@
+use std::{
+    collections::VecDeque,
+    io,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use crate::{rng::Rng, Application, IODriverSimulator, WirelessModemFake};
+
+/// Schedule of packets generated by `TrafficSource`. Times are in milliseconds of virtual time,
+/// counted from the first update of the source.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TrafficPattern {
+    /// Packet every `interval_ms`.
+    ConstantBitRate {
+        interval_ms: u64,
+        packet_length: usize,
+    },
+    /// Packets with exponentially distributed intervals, at least 1 ms apart.
+    Poisson {
+        mean_interval_ms: f64,
+        packet_length: usize,
+    },
+    /// Packet every `interval_ms` during `on_ms`, then silence during `off_ms`, repeatedly.
+    OnOff {
+        on_ms: u64,
+        off_ms: u64,
+        interval_ms: u64,
+        packet_length: usize,
+    },
+    /// Packets at the given times, sorted by time.
+    Replay(Vec<(u64, Vec<u8>)>),
+}
+
+impl TrafficPattern {
+    /// Reads the replay schedule from the file, where every line is time in milliseconds
+    /// and the packet bytes in hex, like `120 48656c6c6f`. Empty lines and lines starting
+    /// with `#` are skipped.
+    /// ```
+    /// use proto_lab::TrafficPattern;
+    ///
+    /// let path = std::env::temp_dir().join("proto_lab_replay_doctest.txt");
+    /// std::fs::write(&path, "# time payload\n20 6869\n5 00ff\n").unwrap();
+    ///
+    /// assert_eq!(
+    ///     TrafficPattern::from_replay_file(&path).unwrap(),
+    ///     TrafficPattern::Replay(vec![(5, vec![0x00, 0xFF]), (20, b"hi".to_vec())])
+    /// );
+    /// ```
+    pub fn from_replay_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        let invalid = |line_number: usize, reason: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Line {}: {}", line_number + 1, reason),
+            )
+        };
+
+        let mut packets = Vec::new();
+        for (line_number, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (time, hex) = line
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| invalid(line_number, "expected time and payload"))?;
+            let time: u64 = time
+                .parse()
+                .map_err(|_| invalid(line_number, "invalid time"))?;
+            let hex = hex.trim();
+            if hex.len() % 2 != 0 {
+                return Err(invalid(line_number, "odd count of hex digits"));
+            }
+            let payload = (0..hex.len())
+                .step_by(2)
+                .map(|index| u8::from_str_radix(&hex[index..index + 2], 16))
+                .collect::<Result<Vec<u8>, _>>()
+                .map_err(|_| invalid(line_number, "invalid hex payload"))?;
+            packets.push((time, payload));
+        }
+        packets.sort_by_key(|(time, _)| *time);
+        Ok(TrafficPattern::Replay(packets))
+    }
+}
+
+/// Figures of the `TrafficSource`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SourceStats {
+    pub generated_packets: u64,
+    pub generated_bytes: u64,
+}
+
+struct SourceState {
+    pattern: TrafficPattern,
+    rng: Rng,
+    started_at: Option<u64>,
+    next_packet_ms: Option<u64>,
+    replay: VecDeque<(u64, Vec<u8>)>,
+    stop_at_ms: Option<u64>,
+    stats: SourceStats,
+}
+
+/// Application, which writes packets into the modem according to the `TrafficPattern`.
+/// Generated packets are filled with the low byte of their index.
+///
+/// Register a clone of it, and keep the other one to read the figures.
+/// ```
+/// use proto_lab::{NetworkSimulator, TrafficPattern, TrafficSink, TrafficSource, WirelessModemFake};
+///
+/// let simulator = NetworkSimulator::new(1);
+/// simulator.create_ether("air");
+/// let mut ether = simulator.get_ether("air").unwrap();
+/// let source_modem = WirelessModemFake::new("source");
+/// let sink_modem = WirelessModemFake::new("sink");
+/// ether.register_driver(source_modem.clone());
+/// ether.register_driver(sink_modem.clone());
+///
+/// let source = TrafficSource::new(
+///     TrafficPattern::ConstantBitRate { interval_ms: 10, packet_length: 4 },
+///     0,
+/// );
+/// let sink = TrafficSink::new();
+/// simulator.register_application(source_modem, source.clone());
+/// simulator.register_application(sink_modem, sink.clone());
+/// simulator.run_ticks(100);
+///
+/// assert_eq!(source.get_stats().generated_packets, 10);
+/// assert_eq!(sink.get_stats().received_bytes, 40);
+/// ```
+pub struct TrafficSource {
+    state: Arc<Mutex<SourceState>>,
+}
+
+impl TrafficSource {
+    /// Creates the source. `seed` makes random patterns reproducible.
+    pub fn new(pattern: TrafficPattern, seed: u64) -> Self {
+        let replay = match &pattern {
+            TrafficPattern::ConstantBitRate { interval_ms: 0, .. }
+            | TrafficPattern::OnOff { interval_ms: 0, .. } => {
+                panic!("Traffic interval can not be 0 ms")
+            }
+            TrafficPattern::OnOff { on_ms: 0, .. } => panic!("Traffic on period can not be 0 ms"),
+            TrafficPattern::Poisson {
+                mean_interval_ms, ..
+            } if !(mean_interval_ms.is_finite() && *mean_interval_ms > 0.0) => {
+                panic!(
+                    "Traffic mean interval shall be positive and finite, not {}",
+                    mean_interval_ms
+                )
+            }
+            TrafficPattern::Replay(packets) => packets.iter().cloned().collect(),
+            _ => VecDeque::new(),
+        };
+        TrafficSource {
+            state: Arc::new(Mutex::new(SourceState {
+                pattern,
+                rng: Rng::new(seed),
+                started_at: None,
+                next_packet_ms: None,
+                replay,
+                stop_at_ms: None,
+                stats: SourceStats::default(),
+            })),
+        }
+    }
+
+    fn lock_state(&self) -> std::sync::MutexGuard<'_, SourceState> {
+        self.state
+            .lock()
+            .expect("Fail to lock traffic source state")
+    }
+
+    /// Stops generating packets after `duration_ms` since the first update.
+    pub fn set_duration(&self, duration_ms: u64) {
+        self.lock_state().stop_at_ms = Some(duration_ms);
+    }
+
+    pub fn get_stats(&self) -> SourceStats {
+        self.lock_state().stats
+    }
+}
+
+impl SourceState {
+    fn packet(&mut self, packet_length: usize) -> Vec<u8> {
+        vec![self.stats.generated_packets as u8; packet_length]
+    }
+
+    /// Draws the interval, which is at least 1 ms, so packets are never due all at once.
+    fn poisson_interval(&mut self, mean_interval_ms: f64) -> u64 {
+        ((-mean_interval_ms * (1.0 - self.rng.next_f64()).ln()).round() as u64).max(1)
+    }
+
+    /// Gives the packets due by `elapsed` milliseconds since the start.
+    fn due_packets(&mut self, elapsed: u64) -> Vec<Vec<u8>> {
+        let mut packets = Vec::new();
+        match self.pattern.clone() {
+            TrafficPattern::ConstantBitRate {
+                interval_ms,
+                packet_length,
+            } => {
+                let next = self.next_packet_ms.get_or_insert(0);
+                while *next <= elapsed {
+                    *next += interval_ms;
+                    packets.push(packet_length);
+                }
+            }
+            TrafficPattern::Poisson {
+                mean_interval_ms,
+                packet_length,
+            } => {
+                if self.next_packet_ms.is_none() {
+                    self.next_packet_ms = Some(self.poisson_interval(mean_interval_ms));
+                }
+                while let Some(next) = self.next_packet_ms.filter(|next| *next <= elapsed) {
+                    self.next_packet_ms = Some(next + self.poisson_interval(mean_interval_ms));
+                    packets.push(packet_length);
+                }
+            }
+            TrafficPattern::OnOff {
+                on_ms,
+                off_ms,
+                interval_ms,
+                packet_length,
+            } => {
+                let next = self.next_packet_ms.get_or_insert(0);
+                while *next <= elapsed {
+                    packets.push(packet_length);
+                    *next += interval_ms;
+                    // Skips the silent part of the period.
+                    let period_offset = *next % (on_ms + off_ms);
+                    if period_offset >= on_ms {
+                        *next += on_ms + off_ms - period_offset;
+                    }
+                }
+            }
+            TrafficPattern::Replay(_) => {
+                let mut replayed = Vec::new();
+                while self
+                    .replay
+                    .front()
+                    .is_some_and(|(time, _)| *time <= elapsed)
+                {
+                    replayed.extend(self.replay.pop_front().map(|(_, payload)| payload));
+                }
+                return replayed;
+            }
+        }
+        packets
+            .into_iter()
+            .map(|packet_length| {
+                let packet = self.packet(packet_length);
+                self.stats.generated_packets += 1;
+                packet
+            })
+            .collect()
+    }
+}
+
+impl Clone for TrafficSource {
+    fn clone(&self) -> Self {
+        TrafficSource {
+            state: Arc::clone(&self.state),
+        }
+    }
+}
+
+impl Application for TrafficSource {
+    fn update(&mut self, modem: &mut WirelessModemFake, now: u64) {
+        let mut state = self.lock_state();
+        let elapsed = now - *state.started_at.get_or_insert(now);
+        if state.stop_at_ms.is_some_and(|stop_at| elapsed >= stop_at) {
+            return;
+        }
+
+        let is_replay = matches!(state.pattern, TrafficPattern::Replay(_));
+        for packet in state.due_packets(elapsed) {
+            if is_replay {
+                state.stats.generated_packets += 1;
+            }
+            state.stats.generated_bytes += packet.len() as u64;
+            for byte in packet {
+                modem.put_to_rx_pin(byte);
+            }
+        }
+    }
+}
+
+/// Figures of the `TrafficSink`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SinkStats {
+    pub received_bytes: u64,
+    pub first_byte_ms: Option<u64>,
+    pub last_byte_ms: Option<u64>,
+}
+
+impl SinkStats {
+    /// Gets count of received bits per second over `duration_ms` of virtual time.
+    pub fn get_goodput_bps(&self, duration_ms: u64) -> f64 {
+        match duration_ms {
+            0 => 0.0,
+            duration_ms => self.received_bytes as f64 * 8.0 * 1000.0 / duration_ms as f64,
+        }
+    }
+}
+
+struct SinkState {
+    stats: SinkStats,
+    arrivals: Vec<(u64, usize)>,
+}
+
+/// Application, which reads everything the modem receives, counting and timestamping it.
+///
+/// Register a clone of it, and keep the other one to read the figures.
+pub struct TrafficSink {
+    state: Arc<Mutex<SinkState>>,
+}
+
+impl TrafficSink {
+    pub fn new() -> Self {
+        TrafficSink {
+            state: Arc::new(Mutex::new(SinkState {
+                stats: SinkStats::default(),
+                arrivals: Vec::new(),
+            })),
+        }
+    }
+
+    fn lock_state(&self) -> std::sync::MutexGuard<'_, SinkState> {
+        self.state.lock().expect("Fail to lock traffic sink state")
+    }
+
+    pub fn get_stats(&self) -> SinkStats {
+        self.lock_state().stats
+    }
+
+    /// Gets virtual times of updates, when bytes were read, with count of the bytes.
+    pub fn get_arrivals(&self) -> Vec<(u64, usize)> {
+        self.lock_state().arrivals.clone()
+    }
+}
+
+impl Default for TrafficSink {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clone for TrafficSink {
+    fn clone(&self) -> Self {
+        TrafficSink {
+            state: Arc::clone(&self.state),
+        }
+    }
+}
+
+impl Application for TrafficSink {
+    fn update(&mut self, modem: &mut WirelessModemFake, now: u64) {
+        let mut count = 0;
+        while modem.get_from_tx_pin().is_some() {
+            count += 1;
+        }
+        if count == 0 {
+            return;
+        }
+
+        let mut state = self.lock_state();
+        state.stats.received_bytes += count as u64;
+        state.stats.first_byte_ms.get_or_insert(now);
+        state.stats.last_byte_ms = Some(now);
+        state.arrivals.push((now, count));
+    }
+}
+
+#[cfg(test)]
+mod traffic_tests {
+    use crate::{NetworkSimulator, TrafficPattern, TrafficSource, WirelessModemFake};
+
+    fn count_packets(pattern: TrafficPattern, ticks: u64) -> u64 {
+        let simulator = NetworkSimulator::new(1);
+        simulator.create_ether("air");
+        let modem = WirelessModemFake::new("source");
+        simulator
+            .get_ether("air")
+            .unwrap()
+            .register_driver(modem.clone());
+        let source = TrafficSource::new(pattern, 7);
+        simulator.register_application(modem, source.clone());
+        simulator.run_ticks(ticks);
+        source.get_stats().generated_packets
+    }
+
+    #[test]
+    fn test_on_off_is_silent_while_off() {
+        let pattern = TrafficPattern::OnOff {
+            on_ms: 10,
+            off_ms: 30,
+            interval_ms: 2,
+            packet_length: 1,
+        };
+        // Packets at 0, 2, .., 8 of every 40 ms period.
+        assert_eq!(count_packets(pattern, 100), 15);
+    }
+
+    #[test]
+    fn test_poisson_rate_is_close_to_mean() {
+        let pattern = TrafficPattern::Poisson {
+            mean_interval_ms: 20.0,
+            packet_length: 1,
+        };
+        let count = count_packets(pattern.clone(), 20_000);
+        assert!((900..1100).contains(&count), "{}", count);
+        assert_eq!(count_packets(pattern, 20_000), count);
+    }
+
+    #[test]
+    fn test_poisson_intervals_are_at_least_one_ms() {
+        let pattern = TrafficPattern::Poisson {
+            mean_interval_ms: 0.001,
+            packet_length: 1,
+        };
+        // Packet every millisecond from 1 ms to 99 ms since the start.
+        assert_eq!(count_packets(pattern, 100), 99);
+    }
+
+    #[test]
+    #[should_panic(expected = "Traffic mean interval shall be positive and finite")]
+    fn test_poisson_mean_interval_of_0_ms_is_refused() {
+        let pattern = TrafficPattern::Poisson {
+            mean_interval_ms: 0.0,
+            packet_length: 1,
+        };
+        TrafficSource::new(pattern, 0);
+    }
+
+    #[test]
+    fn test_replay_writes_payloads_at_their_times() {
+        let modem = WirelessModemFake::new("source");
+        let mut source = TrafficSource::new(
+            TrafficPattern::Replay(vec![(0, vec![1]), (5, vec![2, 3])]),
+            0,
+        );
+        let mut application_modem = modem.clone();
+
+        crate::Application::update(&mut source, &mut application_modem, 100);
+        crate::Application::update(&mut source, &mut application_modem, 104);
+        assert_eq!(source.get_stats().generated_bytes, 1);
+        crate::Application::update(&mut source, &mut application_modem, 105);
+        assert_eq!(source.get_stats().generated_packets, 2);
+        assert_eq!(source.get_stats().generated_bytes, 3);
+    }
+}
@@ -0,0 +1,201 @@
+/// Parity bit of the UART frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Parity {
+    None,
+    /// Count of high data and parity bits is even.
+    Even,
+    /// Count of high data and parity bits is odd.
+    Odd,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopBits {
+    One,
+    Two,
+}
+
+/// Format of the UART frame. Default is 8N1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineConfig {
+    /// Count of data bits, from 5 to 8.
+    pub data_bits: u8,
+    pub parity: Parity,
+    pub stop_bits: StopBits,
+}
+
+impl Default for LineConfig {
+    fn default() -> Self {
+        LineConfig {
+            data_bits: 8,
+            parity: Parity::None,
+            stop_bits: StopBits::One,
+        }
+    }
+}
+
+impl LineConfig {
+    /// Gets count of bits in the frame, including start and stop bits.
+    pub fn get_frame_bits(&self) -> usize {
+        let parity_bits = match self.parity {
+            Parity::None => 0,
+            Parity::Even | Parity::Odd => 1,
+        };
+        let stop_bits = match self.stop_bits {
+            StopBits::One => 1,
+            StopBits::Two => 2,
+        };
+        1 + self.data_bits as usize + parity_bits + stop_bits
+    }
+
+    pub(crate) fn validate(&self) {
+        if !(5..=8).contains(&self.data_bits) {
+            panic!("UART frame can not have {} data bits", self.data_bits);
+        }
+    }
+
+    fn get_parity_bit(&self, byte: u8) -> Option<bool> {
+        let odd_ones = byte.count_ones() % 2 == 1;
+        match self.parity {
+            Parity::None => None,
+            Parity::Even => Some(odd_ones),
+            Parity::Odd => Some(!odd_ones),
+        }
+    }
+}
+
+/// Byte received by UART, with the error flags of its frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ReceivedByte {
+    pub(crate) byte: u8,
+    /// Stop bit was sampled low.
+    pub(crate) framing_error: bool,
+    pub(crate) parity_error: bool,
+    /// Line was low during the whole frame.
+    pub(crate) break_condition: bool,
+}
+
+/// Gives the levels of the frame bits on the line, starting from the start bit.
+/// High level is `true`. Data bits above `data_bits` are not sent.
+pub(crate) fn encode(byte: u8, config: &LineConfig) -> Vec<bool> {
+    let byte = byte & data_mask(config);
+    let mut bits = vec![false];
+    bits.extend((0..config.data_bits).map(|bit| byte & (1 << bit) != 0));
+    bits.extend(config.get_parity_bit(byte));
+    bits.resize(config.get_frame_bits(), true);
+    bits
+}
+
+/// Gives the levels of the line held low for the duration of two frames,
+/// so receivers with longer frames detect it too.
+pub(crate) fn encode_break(config: &LineConfig) -> Vec<bool> {
+    vec![false; 2 * config.get_frame_bits()]
+}
+
+/// Simulates the receiver running at `rx_baud` with `config`, which samples the frame bits
+/// driven at `tx_baud`. The receiver syncs on the start bit and samples in the middle of its bits.
+/// Line is idle (high) after the last bit.
+pub(crate) fn decode(
+    bits: &[bool],
+    tx_baud: u32,
+    rx_baud: u32,
+    config: &LineConfig,
+) -> ReceivedByte {
+    let sample = |rx_index: usize| {
+        // Middle of the bit in units of tx_baud * rx_baud.
+        let sample_time = (2 * rx_index as u64 + 1) * tx_baud as u64;
+        let tx_index = (sample_time / (2 * rx_baud as u64)) as usize;
+        bits.get(tx_index).copied().unwrap_or(true)
+    };
+
+    let mut byte = 0u8;
+    for bit in 0..config.data_bits as usize {
+        if sample(bit + 1) {
+            byte |= 1 << bit;
+        }
+    }
+
+    let mut index = config.data_bits as usize + 1;
+    let parity_error = match config.get_parity_bit(byte) {
+        Some(expected) => {
+            index += 1;
+            sample(index - 1) != expected
+        }
+        None => false,
+    };
+    let framing_error = !sample(index);
+    let break_condition = framing_error && byte == 0 && (1..index).all(|index| !sample(index));
+
+    ReceivedByte {
+        byte,
+        framing_error,
+        parity_error,
+        break_condition,
+    }
+}
+
+/// Simulates the 8N1 byte received by UART running with the other baud rate.
+pub(crate) fn garble(byte: u8, tx_baud: u32, rx_baud: u32) -> u8 {
+    let config = LineConfig::default();
+    decode(&encode(byte, &config), tx_baud, rx_baud, &config).byte
+}
+
+fn data_mask(config: &LineConfig) -> u8 {
+    (0xFFu16 >> (8 - config.data_bits)) as u8
+}
+
+#[cfg(test)]
+mod uart_tests {
+    use super::*;
+
+    #[test]
+    fn test_same_baud_keeps_byte() {
+        let config = LineConfig::default();
+        for byte in 0..=255u8 {
+            let received = decode(&encode(byte, &config), 9600, 9600, &config);
+            assert_eq!(received.byte, byte);
+            assert!(!received.framing_error);
+        }
+    }
+
+    #[test]
+    fn test_faster_receiver_sees_framing_error() {
+        let config = LineConfig::default();
+        let received = decode(&encode(0x00, &config), 4800, 9600, &config);
+        assert!(received.framing_error);
+    }
+
+    #[test]
+    fn test_parity_mismatch() {
+        let even = LineConfig {
+            parity: Parity::Even,
+            ..LineConfig::default()
+        };
+        let odd = LineConfig {
+            parity: Parity::Odd,
+            ..LineConfig::default()
+        };
+        for byte in [0x00, 0x01, 0x7F, 0xFF] {
+            assert!(!decode(&encode(byte, &even), 9600, 9600, &even).parity_error);
+            assert!(decode(&encode(byte, &even), 9600, 9600, &odd).parity_error);
+        }
+    }
+
+    #[test]
+    fn test_seven_data_bits_drop_highest_bit() {
+        let config = LineConfig {
+            data_bits: 7,
+            ..LineConfig::default()
+        };
+        let received = decode(&encode(0xC1, &config), 9600, 9600, &config);
+        assert_eq!(received.byte, 0x41);
+        assert!(!received.framing_error);
+    }
+
+    #[test]
+    fn test_break_is_detected() {
+        let config = LineConfig::default();
+        let received = decode(&encode_break(&config), 9600, 9600, &config);
+        assert!(received.break_condition);
+        assert!(!decode(&encode(0x00, &config), 9600, 9600, &config).break_condition);
+    }
+}